@@ -0,0 +1,119 @@
+//! Object dump read by the generator, the dump is a JSON file describing
+//! the classes, structs, enums and functions of each package
+
+use serde::{Deserialize, Deserializer};
+
+/// Property is a function parameter
+pub const CPF_PARM: u64 = 0x0000_0080;
+/// Property is an out parameter
+pub const CPF_OUT_PARM: u64 = 0x0000_0100;
+/// Property is the return value
+pub const CPF_RETURN_PARM: u64 = 0x0000_0400;
+
+/// Function is an event
+pub const FUNC_EVENT: u64 = 0x0000_0800;
+/// Function is a delegate signature
+pub const FUNC_DELEGATE: u64 = 0x0010_0000;
+
+#[derive(Debug, Deserialize)]
+pub struct Dump {
+    pub packages: Vec<Package>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct Package {
+    /// Name of the package (e.g. "SFXGame")
+    pub name: String,
+    #[serde(default)]
+    pub enums: Vec<Enum>,
+    #[serde(default)]
+    pub structs: Vec<Struct>,
+    #[serde(default)]
+    pub classes: Vec<Struct>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct Enum {
+    pub name: String,
+    pub values: Vec<String>,
+}
+
+/// Class or script struct
+#[derive(Debug, Deserialize)]
+pub struct Struct {
+    pub name: String,
+    /// Name of the class or struct this extends
+    #[serde(rename = "super", default)]
+    pub super_name: Option<String>,
+    /// Size of the super class or struct
+    #[serde(default)]
+    pub super_size: usize,
+    /// Total size including the super class or struct
+    pub size: usize,
+    #[serde(default)]
+    pub fields: Vec<Property>,
+    #[serde(default)]
+    pub functions: Vec<Function>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct Property {
+    pub name: String,
+    /// Type of the property (e.g. "int", "struct:SFXOnlineMOTDInfo",
+    /// "array<object:Actor>"), see [crate::types::PropertyType]
+    #[serde(rename = "type")]
+    pub ty: String,
+    pub offset: usize,
+    /// Size of a single element of the property
+    pub size: usize,
+    #[serde(default = "default_array_dim")]
+    pub array_dim: usize,
+    /// Property flags
+    #[serde(default, deserialize_with = "deserialize_flags")]
+    pub flags: u64,
+    /// Bit within the bitfield for bool properties
+    #[serde(default)]
+    pub bit_mask: Option<u32>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct Function {
+    pub name: String,
+    /// Full name of the function object
+    pub full_name: String,
+    /// Index of the function object within GObjects when dumped
+    #[serde(default)]
+    pub index: Option<usize>,
+    /// Function flags
+    #[serde(default, deserialize_with = "deserialize_flags")]
+    pub flags: u64,
+    /// Parameters including the return value
+    #[serde(default)]
+    pub params: Vec<Property>,
+}
+
+fn default_array_dim() -> usize {
+    1
+}
+
+/// Flags are either numbers or hex strings (e.g. "0x0000000000400000")
+fn deserialize_flags<'de, D>(deserializer: D) -> Result<u64, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum Flags {
+        Number(u64),
+        Hex(String),
+    }
+
+    match Flags::deserialize(deserializer)? {
+        Flags::Number(value) => Ok(value),
+        Flags::Hex(value) => {
+            let digits = value.trim_start_matches("0x").trim_start_matches("0X");
+            u64::from_str_radix(digits, 16)
+                .map_err(|_| serde::de::Error::custom(format!("invalid flags \"{}\"", value)))
+        }
+    }
+}
@@ -0,0 +1,686 @@
+//! Generation of the Rust modules for each package within the dump
+
+use crate::{
+    dump::{
+        Dump, Enum, Function, Package, Property, Struct, CPF_OUT_PARM, CPF_PARM, CPF_RETURN_PARM,
+        FUNC_DELEGATE, FUNC_EVENT,
+    },
+    types::{
+        class_name, enum_name, field_name, module_name, struct_name, InvalidType, PropertyType,
+    },
+};
+use std::{
+    collections::{BTreeMap, BTreeSet, HashMap},
+    fmt::{Display, Write},
+};
+
+/// Classes that are declared by hand within the core module
+const CORE_CLASSES: &[&str] = &["Object", "Field", "Struct", "State", "Class", "Function"];
+
+/// Module generated for a package
+pub struct Module {
+    pub file_name: String,
+    pub contents: String,
+}
+
+#[derive(Debug)]
+pub enum GenerateError {
+    /// Property type couldn't be parsed
+    InvalidType(String, InvalidType),
+    /// Property starts before the end of the previous property
+    Overlap(String),
+    /// Properties extend past the size of the struct
+    Overflow(String),
+    /// Return value isn't the last parameter
+    ReturnNotLast(String),
+}
+
+impl Display for GenerateError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            GenerateError::InvalidType(owner, err) => write!(f, "{}: {}", owner, err),
+            GenerateError::Overlap(owner) => write!(f, "{}: overlapping properties", owner),
+            GenerateError::Overflow(owner) => write!(f, "{}: properties exceed size", owner),
+            GenerateError::ReturnNotLast(owner) => {
+                write!(f, "{}: return value isn't the last parameter", owner)
+            }
+        }
+    }
+}
+
+impl std::error::Error for GenerateError {}
+
+/// Generates a module for each of the packages in the dump, `source`
+/// is the name of the dump noted in the module docs
+pub fn generate(dump: &Dump, source: &str) -> Result<Vec<Module>, GenerateError> {
+    // Module each of the generated types are declared in
+    let mut known: HashMap<String, String> = HashMap::new();
+    for package in &dump.packages {
+        let module = module_name(&package.name);
+        let names = package
+            .classes
+            .iter()
+            .map(|value| class_name(&value.name))
+            .chain(package.structs.iter().map(|value| struct_name(&value.name)))
+            .chain(package.enums.iter().map(|value| enum_name(&value.name)));
+
+        for name in names {
+            known.insert(name, module.clone());
+        }
+    }
+
+    dump.packages
+        .iter()
+        .map(|package| {
+            let mut writer = ModuleWriter::new(package, &known);
+            writer.write_package()?;
+
+            Ok(Module {
+                file_name: format!("{}.rs", writer.module),
+                contents: writer.finish(source),
+            })
+        })
+        .collect()
+}
+
+/// Field of a generated struct
+struct Field {
+    docs: Vec<String>,
+    name: String,
+    ty: String,
+    offset: usize,
+}
+
+struct ModuleWriter<'a> {
+    package: &'a Package,
+    module: String,
+    known: &'a HashMap<String, String>,
+    body: String,
+    /// Names imported from the other SDK modules keyed by module
+    imports: BTreeMap<String, BTreeSet<String>>,
+    /// Names imported from [std::os::raw]
+    raw: BTreeSet<&'static str>,
+}
+
+/// Rounds the value up to a multiple of the alignment
+fn align_to(value: usize, align: usize) -> usize {
+    (value + align - 1) / align * align
+}
+
+impl<'a> ModuleWriter<'a> {
+    fn new(package: &'a Package, known: &'a HashMap<String, String>) -> Self {
+        Self {
+            package,
+            module: module_name(&package.name),
+            known,
+            body: String::new(),
+            imports: BTreeMap::new(),
+            raw: BTreeSet::new(),
+        }
+    }
+
+    fn import(&mut self, module: &str, name: &str) {
+        if module != self.module {
+            self.imports
+                .entry(module.to_string())
+                .or_default()
+                .insert(name.to_string());
+        }
+    }
+
+    fn raw(&mut self, name: &'static str) -> String {
+        self.raw.insert(name);
+        name.to_string()
+    }
+
+    /// Gets the Rust type for a class, imported from the module it's declared in
+    fn class_type(&mut self, name: &str) -> Option<String> {
+        let rust_name = class_name(name);
+        if CORE_CLASSES.contains(&name) {
+            self.import("core", &rust_name);
+            return Some(rust_name);
+        }
+
+        let module = self.known.get(&rust_name)?.clone();
+        self.import(&module, &rust_name);
+        Some(rust_name)
+    }
+
+    /// Gets the Rust type for a script struct, imported from the module it's declared in
+    fn struct_type(&mut self, name: &str) -> Option<String> {
+        let rust_name = struct_name(name);
+        let module = self.known.get(&rust_name)?.clone();
+        self.import(&module, &rust_name);
+        Some(rust_name)
+    }
+
+    /// Gets the Rust type and alignment for a property type, `size` is the
+    /// size of the property used for structs that aren't part of the dump
+    fn rust_type(&mut self, ty: &PropertyType, size: usize) -> (String, usize) {
+        match ty {
+            PropertyType::Byte | PropertyType::Enum(_) => (self.raw("c_uchar"), 1),
+            PropertyType::Int => (self.raw("c_int"), 4),
+            PropertyType::Bool => (self.raw("c_ulong"), 4),
+            PropertyType::Float => (self.raw("c_float"), 4),
+            PropertyType::Name => (self.core("FName"), 4),
+            PropertyType::Str => (self.core("FString"), 4),
+            PropertyType::Delegate => (self.core("FScriptDelegate"), 4),
+            PropertyType::Pointer => (self.core("FPointer"), 4),
+            PropertyType::Object(name) => match self.class_type(name) {
+                Some(value) => (format!("*mut {}", value), 4),
+                None => (format!("*mut {}", self.raw("c_void")), 4),
+            },
+            PropertyType::Interface(_) => (format!("[*mut {}; 2]", self.raw("c_void")), 4),
+            PropertyType::Struct(name) => match self.struct_type(name) {
+                Some(value) => (value, 4),
+                None => (format!("[{}; {:#X}]", self.raw("c_uchar"), size), 1),
+            },
+            // Element size of unknown structs isn't known, the array is left as bytes
+            PropertyType::Array(inner)
+                if matches!(inner.as_ref(), PropertyType::Struct(name)
+                    if !self.known.contains_key(&struct_name(name))) =>
+            {
+                (format!("[{}; {:#X}]", self.raw("c_uchar"), size), 1)
+            }
+            PropertyType::Array(inner) => {
+                let inner = self.rust_type(inner, 0).0;
+                (format!("{}<{}>", self.core("TArray"), inner), 4)
+            }
+        }
+    }
+
+    fn core(&mut self, name: &str) -> String {
+        self.import("core", name);
+        name.to_string()
+    }
+
+    /// Lays out the properties starting at `start`, gaps are filled with
+    /// unknown data and bool properties sharing an offset become a single
+    /// bitfield. Returns the fields, the end offset and the alignment
+    fn layout(
+        &mut self,
+        owner: &str,
+        properties: &[Property],
+        start: usize,
+    ) -> Result<(Vec<Field>, usize, usize), GenerateError> {
+        let mut properties: Vec<&Property> = properties.iter().collect();
+        properties.sort_by_key(|property| property.offset);
+
+        let mut fields: Vec<Field> = Vec::new();
+        let mut names: BTreeSet<String> = BTreeSet::new();
+        let mut cursor = start;
+        let mut align = 1;
+        let mut index = 0;
+
+        while index < properties.len() {
+            let property = properties[index];
+            let owner_name = format!("{}.{}", owner, property.name);
+            let ty: PropertyType = property
+                .ty
+                .parse()
+                .map_err(|err| GenerateError::InvalidType(owner_name.clone(), err))?;
+
+            // Bools at the same offset share a bitfield
+            let mut end = index + 1;
+            if ty == PropertyType::Bool {
+                while properties
+                    .get(end)
+                    .is_some_and(|next| next.offset == property.offset && next.ty == "bool")
+                {
+                    end += 1;
+                }
+            }
+            let group = &properties[index..end];
+            index = end;
+
+            if property.offset < cursor {
+                return Err(GenerateError::Overlap(owner_name));
+            }
+
+            if property.offset > cursor {
+                let padding = self.padding(cursor, property.offset - cursor);
+                fields.push(padding);
+            }
+
+            let (mut rust_type, field_align) = self.rust_type(&ty, property.size);
+            if property.array_dim > 1 {
+                rust_type = format!("[{}; {}]", rust_type, property.array_dim);
+            }
+            align = align.max(field_align);
+
+            let docs = group
+                .iter()
+                .map(|property| {
+                    let mut doc = format!(
+                        "{:#06X} ({:#06X}) [{:#018X}] {} {}",
+                        property.offset,
+                        property.size * property.array_dim,
+                        property.flags,
+                        property.ty,
+                        property.name
+                    );
+                    if let Some(mask) = property.bit_mask {
+                        _ = write!(doc, " ({:#010X})", mask);
+                    }
+                    doc
+                })
+                .collect();
+
+            let base_name = if group.len() > 1 {
+                format!("bitfield_{:04x}", property.offset)
+            } else {
+                field_name(&property.name, ty == PropertyType::Bool)
+            };
+
+            // Names that differ only by case map to the same field name
+            let mut name = base_name.clone();
+            let mut suffix = 2;
+            while !names.insert(name.clone()) {
+                name = format!("{}_{}", base_name, suffix);
+                suffix += 1;
+            }
+
+            fields.push(Field {
+                docs,
+                name,
+                ty: rust_type,
+                offset: property.offset,
+            });
+            cursor = property.offset + property.size * property.array_dim;
+        }
+
+        Ok((fields, cursor, align))
+    }
+
+    /// Creates a field of unknown data filling a gap in the layout
+    fn padding(&mut self, offset: usize, size: usize) -> Field {
+        Field {
+            docs: vec![format!("{:#06X} ({:#06X}) Missing data", offset, size)],
+            name: format!("unknown_data{:04x}", offset),
+            ty: format!("[{}; {:#X}]", self.raw("c_uchar"), size),
+            offset,
+        }
+    }
+
+    fn write_package(&mut self) -> Result<(), GenerateError> {
+        let package = self.package;
+
+        for value in &package.enums {
+            self.write_enum(value);
+        }
+
+        for value in &package.structs {
+            self.write_struct(value, false)?;
+        }
+
+        // Params are prefixed with the class when the function name isn't unique
+        let mut function_names: HashMap<&str, usize> = HashMap::new();
+        for function in package.classes.iter().flat_map(|value| &value.functions) {
+            *function_names.entry(function.name.as_str()).or_default() += 1;
+        }
+
+        for value in &package.classes {
+            self.write_struct(value, true)?;
+            self.write_functions(value, &function_names)?;
+        }
+
+        Ok(())
+    }
+
+    fn write_enum(&mut self, value: &Enum) {
+        let body = &mut self.body;
+        _ = writeln!(body, "// Enum {}.{}", self.package.name, value.name);
+        _ = writeln!(body, "#[allow(non_camel_case_types)]");
+        _ = writeln!(body, "#[derive(Debug, Clone, Copy, PartialEq, Eq)]");
+        _ = writeln!(body, "#[repr(u8)]");
+        _ = writeln!(body, "pub enum {} {{", enum_name(&value.name));
+        for (index, name) in value.values.iter().enumerate() {
+            _ = writeln!(body, "    {} = {},", name, index);
+        }
+        _ = writeln!(body, "}}\n");
+    }
+
+    fn write_struct(&mut self, value: &Struct, is_class: bool) -> Result<(), GenerateError> {
+        let (kind, rust_name) = if is_class {
+            ("Class", class_name(&value.name))
+        } else {
+            ("ScriptStruct", struct_name(&value.name))
+        };
+
+        // Super class or struct is stored as the first field
+        let base = match &value.super_name {
+            Some(name) => {
+                let ty = if is_class {
+                    self.class_type(name)
+                } else {
+                    self.struct_type(name)
+                };
+                Some(ty.unwrap_or_else(|| {
+                    format!("[{}; {:#X}]", self.raw("c_uchar"), value.super_size)
+                }))
+            }
+            None => None,
+        };
+
+        let start = if base.is_some() { value.super_size } else { 0 };
+        let (mut fields, end, align) = self.layout(&value.name, &value.fields, start)?;
+
+        if end > value.size {
+            return Err(GenerateError::Overflow(value.name.clone()));
+        }
+        // Trailing padding from the alignment is left implicit
+        if align_to(end, align.min(4)) != value.size {
+            let padding = self.padding(end, value.size - end);
+            fields.push(padding);
+        }
+
+        if let Some(base) = base {
+            fields.insert(
+                0,
+                Field {
+                    docs: Vec::new(),
+                    name: "_base".to_string(),
+                    ty: base,
+                    offset: 0,
+                },
+            );
+        }
+
+        let body = &mut self.body;
+        _ = writeln!(body, "// {} {}.{}", kind, self.package.name, value.name);
+        _ = writeln!(
+            body,
+            "// {:#06X} ({:#06X} - {:#06X})",
+            value.size - start,
+            value.size,
+            start
+        );
+        _ = writeln!(body, "#[derive(Debug, Clone, Copy)]");
+        _ = writeln!(body, "#[repr(C, packed(4))]");
+        _ = writeln!(body, "pub struct {} {{", rust_name);
+        for field in &fields {
+            for doc in &field.docs {
+                _ = writeln!(body, "    /// {}", doc);
+            }
+            _ = writeln!(body, "    pub {}: {},", field.name, field.ty);
+        }
+        _ = writeln!(body, "}}\n");
+
+        self.write_layout(&rust_name, value.size, &fields);
+
+        if is_class {
+            self.import("core", "StaticClass");
+            let body = &mut self.body;
+            _ = writeln!(body, "unsafe impl StaticClass for {} {{", rust_name);
+            _ = writeln!(body, "    const NAME: &'static str = \"{}\";", value.name);
+            _ = writeln!(body, "}}\n");
+        }
+
+        Ok(())
+    }
+
+    fn write_layout(&mut self, name: &str, size: usize, fields: &[Field]) {
+        self.import("layout", "assert_layout");
+
+        let body = &mut self.body;
+        if fields.is_empty() {
+            _ = writeln!(body, "assert_layout!({}, {:#04X} {{}});\n", name, size);
+            return;
+        }
+
+        _ = writeln!(body, "assert_layout!({}, {:#04X} {{", name, size);
+        for field in fields {
+            _ = writeln!(body, "    {}: {:#04X},", field.name, field.offset);
+        }
+        _ = writeln!(body, "}});\n");
+    }
+
+    fn write_functions(
+        &mut self,
+        class: &Struct,
+        function_names: &HashMap<&str, usize>,
+    ) -> Result<(), GenerateError> {
+        let class_type = class_name(&class.name);
+        let mut methods: Vec<String> = Vec::new();
+
+        // Delegate signatures can't be called directly
+        for function in class
+            .functions
+            .iter()
+            .filter(|function| function.flags & FUNC_DELEGATE == 0)
+        {
+            let params_name = if function_names.get(function.name.as_str()) > Some(&1) {
+                format!("{}{}Params", class.name, function.name)
+            } else {
+                format!("{}Params", function.name)
+            };
+
+            let method_name = if function.flags & FUNC_EVENT != 0 {
+                format!("event_{}", field_name(&function.name, false))
+            } else {
+                field_name(&function.name, false)
+            };
+
+            let method = self.write_params(&class_type, function, &params_name, &method_name)?;
+            methods.push(method);
+        }
+
+        if methods.is_empty() {
+            return Ok(());
+        }
+
+        self.import("params", "define_method");
+
+        let body = &mut self.body;
+        _ = writeln!(body, "impl {} {{", class_type);
+        for method in methods {
+            _ = writeln!(body, "    {}", method);
+        }
+        _ = writeln!(body, "}}\n");
+
+        Ok(())
+    }
+
+    /// Writes the params for the function, returns the method definition
+    fn write_params(
+        &mut self,
+        class_type: &str,
+        function: &Function,
+        params_name: &str,
+        method_name: &str,
+    ) -> Result<String, GenerateError> {
+        let params: Vec<Property> = function
+            .params
+            .iter()
+            .filter(|param| param.flags & CPF_PARM != 0)
+            .map(|param| Property {
+                // Return value name is fixed by define_params!
+                name: if param.flags & CPF_RETURN_PARM != 0 {
+                    "ReturnValue".to_string()
+                } else {
+                    param.name.clone()
+                },
+                ty: param.ty.clone(),
+                offset: param.offset,
+                size: param.size,
+                array_dim: param.array_dim,
+                flags: param.flags,
+                bit_mask: param.bit_mask,
+            })
+            .collect();
+
+        let (mut fields, end, align) = self.layout(&function.full_name, &params, 0)?;
+        let size = align_to(end, align);
+
+        let return_value = match params.iter().position(|param| param.name == "ReturnValue") {
+            Some(_)
+                if fields
+                    .last()
+                    .is_some_and(|field| field.name == "return_value") =>
+            {
+                fields.pop()
+            }
+            Some(_) => return Err(GenerateError::ReturnNotLast(function.full_name.clone())),
+            None => None,
+        };
+
+        self.import("params", "define_params");
+
+        let body = &mut self.body;
+        _ = writeln!(body, "define_params! {{");
+        _ = writeln!(body, "    /// Params for [{}::{}]", class_type, method_name);
+        if let Some(index) = function.index {
+            _ = writeln!(body, "    ///");
+            _ = writeln!(body, "    /// GObjects index {} when dumped", index);
+        }
+        _ = write!(
+            body,
+            "    pub struct {} = \"{}\"",
+            params_name, function.full_name
+        );
+        if fields.is_empty() {
+            _ = write!(body, " {{}}");
+        } else {
+            _ = writeln!(body, " {{");
+            for field in &fields {
+                _ = writeln!(body, "        {}: {},", field.name, field.ty);
+            }
+            _ = write!(body, "    }}");
+        }
+        match &return_value {
+            Some(value) => _ = writeln!(body, " -> {}", value.ty),
+            None => _ = writeln!(body),
+        }
+        _ = writeln!(body, "}}\n");
+
+        let mut layout_fields: Vec<Field> = fields
+            .iter()
+            .map(|field| Field {
+                docs: Vec::new(),
+                name: field.name.clone(),
+                ty: field.ty.clone(),
+                offset: field.offset,
+            })
+            .collect();
+        layout_fields.extend(return_value);
+        self.write_layout(params_name, size, &layout_fields);
+
+        // Out params are passed by reference, padding is left zeroed
+        let is_out = |field: &Field| {
+            params.iter().any(|param| {
+                param.offset == field.offset
+                    && param.flags & CPF_OUT_PARM != 0
+                    && param.flags & CPF_RETURN_PARM == 0
+            })
+        };
+        let args: Vec<String> = fields
+            .iter()
+            .filter(|field| !field.name.starts_with("unknown_data") && !is_out(field))
+            .map(|field| format!("{}: {}", field.name, field.ty))
+            .collect();
+        let outs: Vec<String> = fields
+            .iter()
+            .filter(|field| is_out(field))
+            .map(|field| format!("{}: {}", field.name, field.ty))
+            .collect();
+
+        let mut items = vec![method_name.to_string(), params_name.to_string()];
+        items.extend(args);
+        let mut method = items.join(", ");
+        if !outs.is_empty() {
+            _ = write!(method, "; out {}", outs.join(", "));
+        }
+
+        // rustfmt leaves macro calls as is, long calls are split like a function call
+        if method.len() + 21 <= 100 {
+            return Ok(format!("define_method!({});", method));
+        }
+
+        let mut method = format!("define_method!(\n        {}", items.join(",\n        "));
+        if !outs.is_empty() {
+            _ = write!(method, ";\n        out {}", outs.join(",\n        "));
+        }
+        method.push_str("\n    );");
+        Ok(method)
+    }
+
+    /// Creates the module contents with the imports
+    fn finish(self, source: &str) -> String {
+        let mut out = String::new();
+        _ = writeln!(
+            out,
+            "//! Package {}, generated by sdk-gen from {}. Don't edit by hand\n",
+            self.package.name, source
+        );
+
+        let imports: Vec<String> = self
+            .imports
+            .iter()
+            .map(|(module, names)| {
+                let names: Vec<&str> = names.iter().map(String::as_str).collect();
+                format!("{}::{}", module, use_list(&names))
+            })
+            .collect();
+        match imports.as_slice() {
+            [] => {}
+            [import] => _ = writeln!(out, "use super::{};", import),
+            imports => {
+                _ = writeln!(out, "use super::{{");
+                for import in imports {
+                    _ = writeln!(out, "    {},", import);
+                }
+                _ = writeln!(out, "}};");
+            }
+        }
+
+        if !self.raw.is_empty() {
+            let names: Vec<&str> = self.raw.iter().copied().collect();
+            _ = writeln!(out, "use std::os::raw::{};", use_list(&names));
+        }
+
+        out.push('\n');
+        out.push_str(self.body.trim_end());
+        out.push('\n');
+        out
+    }
+}
+
+/// Creates the list of names for a use declaration, braces are only
+/// used for more than one name to match rustfmt
+fn use_list(names: &[&str]) -> String {
+    match names {
+        [name] => name.to_string(),
+        names => format!("{{{}}}", names.join(", ")),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::Path;
+
+    /// Checks the generated modules match the SDK modules, the generator
+    /// output is compared directly so rustfmt isn't needed
+    #[test]
+    fn matches_sdk() {
+        let manifest = Path::new(env!("CARGO_MANIFEST_DIR"));
+        let dir = manifest.join("..").join("src").join("sdk");
+        let sample = manifest.join("sample");
+        let dump = std::fs::read_to_string(sample.join("dump.json")).unwrap();
+        let dump: Dump = serde_json::from_str(&dump).unwrap();
+
+        let modules = generate(&dump, "dump.json").unwrap();
+        assert_eq!(modules.len(), 2);
+
+        for module in modules {
+            let path = dir.join(&module.file_name);
+            let expected = std::fs::read_to_string(&path).unwrap();
+            assert!(
+                module.contents == expected,
+                "{} doesn't match the generated module:\n{}",
+                path.display(),
+                module.contents
+            );
+        }
+    }
+}
@@ -0,0 +1,106 @@
+//! Generates the Rust SDK modules from an object dump of the game
+//!
+//! ```text
+//! sdk-gen <dump.json> <output dir> [--check]
+//! ```
+//!
+//! With `--check` nothing is written, the generated modules are compared
+//! against the existing files in the output directory instead
+
+use dump::Dump;
+use std::{
+    io::Write,
+    path::{Path, PathBuf},
+    process::{Command, ExitCode, Stdio},
+};
+
+mod dump;
+mod generate;
+mod types;
+
+fn main() -> ExitCode {
+    let mut check = false;
+    let mut paths: Vec<PathBuf> = Vec::new();
+    for arg in std::env::args().skip(1) {
+        if arg == "--check" {
+            check = true;
+        } else {
+            paths.push(PathBuf::from(arg));
+        }
+    }
+
+    let [dump_path, output] = paths.as_slice() else {
+        eprintln!("Usage: sdk-gen <dump.json> <output dir> [--check]");
+        return ExitCode::FAILURE;
+    };
+
+    match run(dump_path, output, check) {
+        Ok(true) => ExitCode::SUCCESS,
+        Ok(false) => ExitCode::FAILURE,
+        Err(err) => {
+            eprintln!("error: {}", err);
+            ExitCode::FAILURE
+        }
+    }
+}
+
+/// Generates the modules, returns false when checking and the
+/// existing modules are out of date
+fn run(dump_path: &Path, output: &Path, check: bool) -> Result<bool, Box<dyn std::error::Error>> {
+    let dump = std::fs::read_to_string(dump_path)
+        .map_err(|err| format!("failed to read {}: {}", dump_path.display(), err))?;
+    let dump: Dump = serde_json::from_str(&dump)?;
+
+    let source = dump_path
+        .file_name()
+        .map(|value| value.to_string_lossy().to_string())
+        .unwrap_or_default();
+    let modules = generate::generate(&dump, &source)?;
+
+    let mut up_to_date = true;
+
+    for module in modules {
+        let path = output.join(&module.file_name);
+        let contents = format_source(&module.contents)?;
+
+        if check {
+            let existing = std::fs::read_to_string(&path).unwrap_or_default();
+            if existing != contents {
+                eprintln!("{} is out of date", path.display());
+                up_to_date = false;
+            }
+            continue;
+        }
+
+        std::fs::create_dir_all(output)?;
+        std::fs::write(&path, contents)?;
+        println!("Generated {}", path.display());
+    }
+
+    Ok(up_to_date)
+}
+
+/// Formats the generated source with rustfmt. The generator output is
+/// already formatted for simple modules, rustfmt is still required so the
+/// output doesn't depend on whether it's available
+fn format_source(source: &str) -> Result<String, String> {
+    let mut child = Command::new("rustfmt")
+        .args(["--edition", "2021"])
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::null())
+        .spawn()
+        .map_err(|err| format!("failed to run rustfmt: {}", err))?;
+
+    if let Some(mut stdin) = child.stdin.take() {
+        _ = stdin.write_all(source.as_bytes());
+    }
+
+    match child.wait_with_output() {
+        Ok(output) if output.status.success() => {
+            Ok(String::from_utf8_lossy(&output.stdout).to_string())
+        }
+        Ok(output) => Err(format!("rustfmt failed: {}", output.status)),
+        Err(err) => Err(format!("failed to run rustfmt: {}", err)),
+    }
+}
@@ -0,0 +1,164 @@
+//! Property types from the dump and naming of the generated items
+
+use std::{fmt::Display, str::FromStr};
+
+/// Type of a property, parsed from the dump type strings:
+///
+/// `byte`, `int`, `bool`, `float`, `name`, `str`, `delegate`, `pointer`,
+/// `object:<Class>`, `interface:<Class>`, `struct:<Struct>`, `enum:<Enum>`
+/// and `array<type>`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PropertyType {
+    Byte,
+    Int,
+    Bool,
+    Float,
+    Name,
+    Str,
+    Delegate,
+    Pointer,
+    Object(String),
+    Interface(String),
+    Struct(String),
+    Enum(String),
+    Array(Box<PropertyType>),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct InvalidType(pub String);
+
+impl Display for InvalidType {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "invalid property type \"{}\"", self.0)
+    }
+}
+
+impl std::error::Error for InvalidType {}
+
+impl FromStr for PropertyType {
+    type Err = InvalidType;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let value = s.trim();
+
+        if let Some(inner) = value
+            .strip_prefix("array<")
+            .and_then(|value| value.strip_suffix('>'))
+        {
+            return Ok(PropertyType::Array(Box::new(inner.parse()?)));
+        }
+
+        let ty = match value.split_once(':') {
+            Some(("object", name)) => PropertyType::Object(name.to_string()),
+            Some(("interface", name)) => PropertyType::Interface(name.to_string()),
+            Some(("struct", name)) => PropertyType::Struct(name.to_string()),
+            Some(("enum", name)) => PropertyType::Enum(name.to_string()),
+            Some(_) => return Err(InvalidType(s.to_string())),
+            None => match value {
+                "byte" => PropertyType::Byte,
+                "int" => PropertyType::Int,
+                "bool" => PropertyType::Bool,
+                "float" => PropertyType::Float,
+                "name" => PropertyType::Name,
+                "str" => PropertyType::Str,
+                "delegate" => PropertyType::Delegate,
+                "pointer" => PropertyType::Pointer,
+                _ => return Err(InvalidType(s.to_string())),
+            },
+        };
+
+        Ok(ty)
+    }
+}
+
+/// Rust keywords that can't be used as field or method names
+const KEYWORDS: &[&str] = &[
+    "abstract", "as", "async", "await", "become", "box", "break", "const", "continue", "crate",
+    "do", "dyn", "else", "enum", "extern", "false", "final", "fn", "for", "if", "impl", "in",
+    "let", "loop", "macro", "match", "mod", "move", "mut", "override", "priv", "pub", "ref",
+    "return", "self", "static", "struct", "super", "trait", "true", "try", "typeof", "unsafe",
+    "unsized", "use", "virtual", "where", "while", "yield",
+];
+
+/// Converts an UnrealScript name to snake case (e.g. "APIName" to "api_name")
+pub fn snake_case(value: &str) -> String {
+    let chars: Vec<char> = value.chars().collect();
+    let mut out = String::with_capacity(value.len() + 4);
+
+    for (index, value) in chars.iter().enumerate() {
+        if !value.is_ascii_uppercase() {
+            out.push(*value);
+            continue;
+        }
+
+        let previous = index.checked_sub(1).map(|index| chars[index]);
+        let next = chars.get(index + 1);
+
+        // Word boundaries are lower to upper and the last upper of an acronym
+        let is_boundary = match previous {
+            Some(previous) if previous.is_ascii_lowercase() || previous.is_ascii_digit() => true,
+            Some(previous) if previous.is_ascii_uppercase() => {
+                next.is_some_and(|next| next.is_ascii_lowercase())
+            }
+            _ => false,
+        };
+
+        if is_boundary && !out.ends_with('_') {
+            out.push('_');
+        }
+
+        out.push(value.to_ascii_lowercase());
+    }
+
+    out
+}
+
+/// Creates a field or method name from an UnrealScript name, bool
+/// properties have their "b" prefix removed
+pub fn field_name(value: &str, is_bool: bool) -> String {
+    let value = match value.strip_prefix('b') {
+        Some(rest) if is_bool && rest.starts_with(|c: char| c.is_ascii_uppercase()) => rest,
+        _ => value,
+    };
+
+    // Leading underscores are kept, repeated ones within the name are collapsed
+    let name = snake_case(value);
+    let rest = name.trim_start_matches('_');
+    let mut name = "_".repeat(name.len() - rest.len());
+    for part in rest.split('_').filter(|part| !part.is_empty()) {
+        if !name.ends_with('_') && !name.is_empty() {
+            name.push('_');
+        }
+        name.push_str(part);
+    }
+
+    match name.as_str() {
+        "type" => "ty".to_string(),
+        name if KEYWORDS.contains(&name) => format!("{}_", name),
+        _ => name,
+    }
+}
+
+/// Name of the generated struct for a class
+pub fn class_name(name: &str) -> String {
+    format!("U{}", name)
+}
+
+/// Name of the generated struct for a script struct
+pub fn struct_name(name: &str) -> String {
+    format!("F{}", name)
+}
+
+/// Name of the generated enum
+pub fn enum_name(name: &str) -> String {
+    if name.starts_with('E') {
+        name.to_string()
+    } else {
+        format!("E{}", name)
+    }
+}
+
+/// Name of the module generated for a package
+pub fn module_name(package: &str) -> String {
+    package.to_ascii_lowercase()
+}
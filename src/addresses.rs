@@ -0,0 +1,191 @@
+//! Addresses of the game functions and globals, resolved at startup
+//! by scanning the game executable for signatures
+
+use crate::{
+    config::{parse_address, OffsetConfig, OffsetsConfig},
+    error, info,
+    scanner::{Deref, Module, ScanError, Signature},
+    warn,
+};
+
+/// Address of ProcessEvent in the original ME3 executable, only used to
+/// pick between matches of the signature, never used on its own
+const PROCESS_EVENT_KNOWN: usize = 0x00453120;
+
+/// Address of the GObjects array in the original ME3 executable, only used
+/// to pick between matches of the signature, never used on its own
+const GAME_OBJECTS_KNOWN: usize = 0x01AB5634;
+
+/// Prologue of UObject::ProcessEvent up to `mov esi, ecx`. Other functions
+/// share the same SEH prologue so on its own this can match more than once,
+/// a match is only accepted when it's the only one or when it's the known
+/// address
+const PROCESS_EVENT_PATTERN: &str = "55 8B EC 6A FF 68 ?? ?? ?? ?? 64 A1 00 00 00 00 50 83 EC ?? \
+     A1 ?? ?? ?? ?? 33 C5 89 45 F0 53 56 57 50 8D 45 F4 64 A3 00 00 00 00 8B F1";
+
+/// Indexed load from the GObjects data pointer `mov ecx, [GObjects]`
+/// followed by `mov eax, [ecx+esi*4]` and `test eax, eax`, the address is
+/// the operand of the first instruction. This is found at many of the
+/// object lookups, they must all load from the same address.
+const GAME_OBJECTS_PATTERN: &str = "8B 0D ?? ?? ?? ?? 8B 04 B1 85 C0";
+
+/// Resolved addresses for the game, [None] when the address couldn't be
+/// resolved
+#[derive(Debug, Default, Clone, Copy)]
+pub struct Addresses {
+    /// UObject::ProcessEvent function
+    pub process_event: Option<usize>,
+    /// GObjects array of all the game objects
+    pub game_objects: Option<usize>,
+    /// GNames array of all the name entries, only available when
+    /// provided by the config
+    pub game_names: Option<usize>,
+    /// GMalloc global pointer to the game allocator, only available
+    /// when provided by the config
+    pub gmalloc: Option<usize>,
+}
+
+/// Address that should be resolved at startup
+struct AddressTarget<'a> {
+    /// Name of the address for logging
+    name: &'static str,
+    /// Built-in signature for the address
+    signature: Option<Signature>,
+    /// Address in the original executable, chosen when the signature
+    /// matches more than one address and this is one of them
+    known: Option<usize>,
+    /// Override from the config
+    config: Option<&'a OffsetConfig>,
+}
+
+impl AddressTarget<'_> {
+    /// Resolves the address against the module, [None] when the address
+    /// isn't configured and the signature doesn't resolve to a single
+    /// address
+    unsafe fn resolve(self, module: Option<&Module>) -> Option<usize> {
+        let signature = match self.config {
+            // Fixed address from the config is used as-is
+            Some(OffsetConfig::Address { address }) => {
+                if let Some(address) = parse_address(address) {
+                    info!("Using configured {} address {:#010X}", self.name, address);
+                    return Some(address);
+                }
+                self.signature
+            }
+            Some(OffsetConfig::Signature {
+                pattern,
+                offset,
+                deref,
+            }) => match Signature::new(pattern, *offset, *deref) {
+                Ok(value) => Some(value),
+                Err(err) => {
+                    error!("Invalid {} signature: {}", self.name, err);
+                    self.signature
+                }
+            },
+            None => self.signature,
+        };
+
+        let (signature, module) = match (signature, module) {
+            (Some(signature), Some(module)) => (signature, module),
+            (Some(_), None) => {
+                error!("Failed to resolve {}: main module unavailable", self.name);
+                return None;
+            }
+            (None, _) => return None,
+        };
+
+        let result = module.resolve_all(&signature).and_then(|addresses| {
+            // Resolved addresses must be within the executable image
+            let addresses: Vec<usize> = addresses
+                .into_iter()
+                .filter(|address| module.contains(*address))
+                .collect();
+
+            match addresses.as_slice() {
+                [] => Err(ScanError::NotFound),
+                [address] => Ok(*address),
+                _ => match self.known {
+                    Some(known) if addresses.contains(&known) => {
+                        info!(
+                            "{} signature matched {} addresses, using known address",
+                            self.name,
+                            addresses.len()
+                        );
+                        Ok(known)
+                    }
+                    _ => Err(ScanError::Ambiguous(addresses.len())),
+                },
+            }
+        });
+
+        match result {
+            Ok(address) => {
+                info!("Resolved {} at {:#010X}", self.name, address);
+                Some(address)
+            }
+            Err(err) => {
+                error!("Failed to resolve {}: {}", self.name, err);
+                None
+            }
+        }
+    }
+}
+
+impl Addresses {
+    /// Resolves the addresses by scanning the main executable module
+    ///
+    /// # Safety
+    ///
+    /// Reads the memory of the main executable module
+    pub unsafe fn resolve(config: &OffsetsConfig) -> Self {
+        let module = Module::main();
+        if module.is_none() {
+            warn!("Failed to read main module headers, only configured addresses are used");
+        }
+        let module = module.as_ref();
+
+        let process_event = AddressTarget {
+            name: "ProcessEvent",
+            signature: Some(
+                Signature::new(PROCESS_EVENT_PATTERN, 0, Deref::None)
+                    .expect("Invalid built-in signature"),
+            ),
+            known: Some(PROCESS_EVENT_KNOWN),
+            config: config.process_event.as_ref(),
+        };
+
+        let game_objects = AddressTarget {
+            name: "GObjects",
+            signature: Some(
+                Signature::new(GAME_OBJECTS_PATTERN, 2, Deref::Absolute)
+                    .expect("Invalid built-in signature"),
+            ),
+            known: Some(GAME_OBJECTS_KNOWN),
+            config: config.game_objects.as_ref(),
+        };
+
+        // No known signature for GNames, only resolved from the config
+        let game_names = AddressTarget {
+            name: "GNames",
+            signature: None,
+            known: None,
+            config: config.game_names.as_ref(),
+        };
+
+        // No known signature for GMalloc, only resolved from the config
+        let gmalloc = AddressTarget {
+            name: "GMalloc",
+            signature: None,
+            known: None,
+            config: config.gmalloc.as_ref(),
+        };
+
+        Self {
+            process_event: process_event.resolve(module),
+            game_objects: game_objects.resolve(module),
+            game_names: game_names.resolve(module),
+            gmalloc: gmalloc.resolve(module),
+        }
+    }
+}
@@ -0,0 +1,474 @@
+//! Runtime configuration for the plugin, loaded from a JSON file
+//! next to the game executable
+
+use crate::{logging::LogLevel, messages::ack::HttpUrl, scanner::Deref, scanner::Pattern};
+use serde::{Deserialize, Serialize};
+use std::{fmt::Display, path::PathBuf, sync::OnceLock};
+
+/// Name of the config file placed next to the game executable
+const CONFIG_FILE_NAME: &str = "deep-link.json";
+
+/// Loaded configuration
+static CONFIG: OnceLock<Config> = OnceLock::new();
+
+/// Gets the loaded configuration, defaults are used when
+/// the configuration hasn't been loaded
+pub fn config() -> &'static Config {
+    CONFIG.get_or_init(Config::default)
+}
+
+/// Loads the configuration and stores it globally, returns any errors
+/// that occurred so they can be logged once logging is available
+pub fn init() -> Vec<ConfigError> {
+    let (config, errors) = Config::load();
+    _ = CONFIG.set(config);
+    errors
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(default)]
+pub struct Config {
+    /// Whether to allocate a console window for log output
+    pub console: bool,
+    /// Maximum level of log messages
+    pub log_level: LogLevel,
+    /// Number of panics within hooks before custom handling is disabled,
+    /// zero never disables custom handling
+    pub panic_limit: u32,
+    /// Event dumping options
+    pub events: EventsConfig,
+    /// Overrides for the game addresses
+    pub offsets: OffsetsConfig,
+    /// Message protocol options
+    pub messages: MessagesConfig,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            console: true,
+            log_level: LogLevel::default(),
+            panic_limit: 3,
+            events: EventsConfig::default(),
+            offsets: OffsetsConfig::default(),
+            messages: MessagesConfig::default(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(default)]
+pub struct EventsConfig {
+    /// Whether to trace processed events to a file
+    pub dump: bool,
+    /// Path of the JSONL file to trace events to, relative to the game executable
+    pub path: String,
+    /// Only trace events whose function full name matches one of these
+    /// glob patterns, all events are included when empty
+    pub include: Vec<String>,
+    /// Don't trace events whose function full name matches one of these
+    /// glob patterns
+    pub exclude: Vec<String>,
+    /// Only trace every Nth call of each function, 1 traces every call
+    pub sample_rate: u32,
+    /// Maximum number of trace records buffered before they are written,
+    /// records are dropped when the buffer is full
+    pub buffer_capacity: usize,
+    /// Path of the file to write the per-function hit counts to when
+    /// unloading, relative to the game executable
+    pub counts_path: Option<String>,
+}
+
+impl Default for EventsConfig {
+    fn default() -> Self {
+        Self {
+            dump: true,
+            path: "event-dump.jsonl".to_string(),
+            include: Vec::new(),
+            exclude: Vec::new(),
+            sample_rate: 1,
+            buffer_capacity: 8192,
+            counts_path: Some("event-counts.json".to_string()),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+#[serde(default)]
+pub struct OffsetsConfig {
+    /// Override for the ProcessEvent function address
+    pub process_event: Option<OffsetConfig>,
+    /// Override for the GObjects array address
+    pub game_objects: Option<OffsetConfig>,
+    /// Address of the GNames array, names can't be looked up without it
+    pub game_names: Option<OffsetConfig>,
+    /// Address of the GMalloc global, without it engine arrays can't be
+    /// grown and strings and arrays created by the plugin use the Rust
+    /// allocator
+    pub gmalloc: Option<OffsetConfig>,
+}
+
+/// Override for an address, either a fixed address or a signature
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(untagged)]
+pub enum OffsetConfig {
+    /// Fixed address as a hex string (e.g. "0x00453120")
+    Address { address: String },
+    /// Signature to scan for
+    Signature {
+        /// IDA style pattern (e.g. "8B 4C 24 ?? 56")
+        pattern: String,
+        /// Offset from the start of the match
+        #[serde(default)]
+        offset: isize,
+        /// Dereference to apply to the match
+        #[serde(default)]
+        deref: Deref,
+    },
+}
+
+/// Parses a hex address with an optional "0x" prefix
+pub fn parse_address(value: &str) -> Option<usize> {
+    let value = value
+        .strip_prefix("0x")
+        .or_else(|| value.strip_prefix("0X"))
+        .unwrap_or(value);
+    usize::from_str_radix(value, 16).ok()
+}
+
+impl OffsetConfig {
+    fn validate(&self, name: &'static str) -> Result<(), ConfigError> {
+        match self {
+            OffsetConfig::Address { address } => match parse_address(address) {
+                Some(0) | None => Err(ConfigError::InvalidAddress(name, address.clone())),
+                Some(_) => Ok(()),
+            },
+            OffsetConfig::Signature { pattern, .. } => pattern
+                .parse::<Pattern>()
+                .map(|_| ())
+                .map_err(|err| ConfigError::InvalidPattern(name, err.to_string())),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(default)]
+pub struct MessagesConfig {
+    /// Whether to handle system messages from the server
+    pub enabled: bool,
+    /// Prefix identifying a system message within the message of the day
+    pub prefix: String,
+    /// URL that displayed confirmation codes are acknowledged to, only
+    /// plain `http://` URLs are supported
+    pub ack_url: Option<String>,
+    /// Minimum milliseconds between displaying system messages
+    pub display_interval_ms: u64,
+    /// Maximum number of system messages waiting to be displayed
+    pub queue_capacity: usize,
+}
+
+impl Default for MessagesConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            prefix: "[SYSTEM_TERMINAL]".to_string(),
+            ack_url: None,
+            display_interval_ms: 5000,
+            queue_capacity: 32,
+        }
+    }
+}
+
+/// Errors from loading the configuration
+#[derive(Debug)]
+pub enum ConfigError {
+    /// Config file exists but couldn't be read
+    Read(std::io::Error),
+    /// Config file isn't valid
+    Parse(serde_json::Error),
+    /// Config contains a key that isn't used, most likely misspelled
+    UnknownKey(String),
+    /// Address override isn't a valid hex address
+    InvalidAddress(&'static str, String),
+    /// Signature override pattern isn't valid
+    InvalidPattern(&'static str, String),
+    /// Event dump is enabled without a path
+    MissingEventPath,
+    /// Event sample rate is zero
+    InvalidSampleRate,
+    /// Event buffer capacity is zero
+    InvalidBufferCapacity,
+    /// System message prefix is empty
+    EmptyMessagePrefix,
+    /// Acknowledgement URL isn't a valid `http://` URL
+    InvalidAckUrl(String),
+    /// System message queue capacity is zero
+    InvalidQueueCapacity,
+}
+
+impl Display for ConfigError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ConfigError::Read(err) => write!(f, "failed to read config: {}", err),
+            ConfigError::Parse(err) => write!(f, "failed to parse config: {}", err),
+            ConfigError::UnknownKey(key) => write!(f, "{}: unknown key", key),
+            ConfigError::InvalidAddress(name, value) => {
+                write!(f, "offsets.{}: invalid address \"{}\"", name, value)
+            }
+            ConfigError::InvalidPattern(name, err) => write!(f, "offsets.{}: {}", name, err),
+            ConfigError::MissingEventPath => f.write_str("events.path: must not be empty"),
+            ConfigError::InvalidSampleRate => {
+                f.write_str("events.sample_rate: must be greater than zero")
+            }
+            ConfigError::InvalidBufferCapacity => {
+                f.write_str("events.buffer_capacity: must be greater than zero")
+            }
+            ConfigError::EmptyMessagePrefix => f.write_str("messages.prefix: must not be empty"),
+            ConfigError::InvalidAckUrl(value) => {
+                write!(f, "messages.ack_url: invalid http URL \"{}\"", value)
+            }
+            ConfigError::InvalidQueueCapacity => {
+                f.write_str("messages.queue_capacity: must be greater than zero")
+            }
+        }
+    }
+}
+
+impl std::error::Error for ConfigError {}
+
+/// Gets the path to a file next to the game executable
+pub fn game_relative_path(name: &str) -> PathBuf {
+    std::env::current_exe()
+        .ok()
+        .and_then(|path| path.parent().map(|parent| parent.join(name)))
+        .unwrap_or_else(|| PathBuf::from(name))
+}
+
+/// Finds the keys of the value that aren't present in the known value, only
+/// objects of the known value are checked as optional values default to null
+fn unknown_keys(
+    value: &serde_json::Value,
+    known: &serde_json::Value,
+    path: &str,
+    errors: &mut Vec<ConfigError>,
+) {
+    let (Some(value), Some(known)) = (value.as_object(), known.as_object()) else {
+        return;
+    };
+
+    for (key, value) in value {
+        let key_path = if path.is_empty() {
+            key.clone()
+        } else {
+            format!("{}.{}", path, key)
+        };
+
+        match known.get(key) {
+            Some(known) => unknown_keys(value, known, &key_path, errors),
+            None => errors.push(ConfigError::UnknownKey(key_path)),
+        }
+    }
+}
+
+impl Config {
+    /// Loads the config file from next to the game executable, falling back
+    /// to the defaults for anything that is missing or invalid
+    pub fn load() -> (Config, Vec<ConfigError>) {
+        let path = game_relative_path(CONFIG_FILE_NAME);
+
+        match std::fs::read_to_string(path) {
+            Ok(value) => Config::parse(&value),
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => {
+                (Config::default(), Vec::new())
+            }
+            Err(err) => (Config::default(), vec![ConfigError::Read(err)]),
+        }
+    }
+
+    /// Parses and validates the config JSON, unknown keys are reported
+    /// but don't prevent the rest of the config from loading
+    pub fn parse(value: &str) -> (Config, Vec<ConfigError>) {
+        let value: serde_json::Value = match serde_json::from_str(value) {
+            Ok(value) => value,
+            Err(err) => return (Config::default(), vec![ConfigError::Parse(err)]),
+        };
+
+        let mut errors = Vec::new();
+        if let Ok(known) = serde_json::to_value(Config::default()) {
+            unknown_keys(&value, &known, "", &mut errors);
+        }
+
+        match serde_json::from_value::<Config>(value) {
+            Ok(mut config) => {
+                errors.extend(config.validate());
+                (config, errors)
+            }
+            Err(err) => (Config::default(), vec![ConfigError::Parse(err)]),
+        }
+    }
+
+    /// Validates the config, invalid values are replaced with
+    /// their defaults and the errors are returned
+    pub fn validate(&mut self) -> Vec<ConfigError> {
+        let mut errors = Vec::new();
+
+        for (name, offset) in [
+            ("process_event", &mut self.offsets.process_event),
+            ("game_objects", &mut self.offsets.game_objects),
+            ("game_names", &mut self.offsets.game_names),
+            ("gmalloc", &mut self.offsets.gmalloc),
+        ] {
+            if let Some(Err(err)) = offset.as_ref().map(|value| value.validate(name)) {
+                errors.push(err);
+                *offset = None;
+            }
+        }
+
+        if self.events.dump && self.events.path.trim().is_empty() {
+            errors.push(ConfigError::MissingEventPath);
+            self.events.path = EventsConfig::default().path;
+        }
+
+        if self.events.sample_rate == 0 {
+            errors.push(ConfigError::InvalidSampleRate);
+            self.events.sample_rate = 1;
+        }
+
+        if self.events.buffer_capacity == 0 {
+            errors.push(ConfigError::InvalidBufferCapacity);
+            self.events.buffer_capacity = EventsConfig::default().buffer_capacity;
+        }
+
+        if self.messages.prefix.is_empty() {
+            errors.push(ConfigError::EmptyMessagePrefix);
+            self.messages.prefix = MessagesConfig::default().prefix;
+        }
+
+        if let Some(url) = &self.messages.ack_url {
+            if HttpUrl::parse(url).is_none() {
+                errors.push(ConfigError::InvalidAckUrl(url.clone()));
+                self.messages.ack_url = None;
+            }
+        }
+
+        if self.messages.queue_capacity == 0 {
+            errors.push(ConfigError::InvalidQueueCapacity);
+            self.messages.queue_capacity = MessagesConfig::default().queue_capacity;
+        }
+
+        errors
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn defaults() {
+        let (config, errors) = Config::parse(r#"{"events": {"sample_rate": 4}}"#);
+        assert!(errors.is_empty(), "{:?}", errors);
+
+        // Missing values are filled in from the defaults
+        assert_eq!(config.events.sample_rate, 4);
+        assert_eq!(config.events.path, EventsConfig::default().path);
+        assert_eq!(config.log_level, LogLevel::Info);
+        assert_eq!(config.panic_limit, 3);
+        assert_eq!(config.messages.queue_capacity, 32);
+        assert!(config.offsets.process_event.is_none());
+
+        let (_, errors) = Config::parse("{}");
+        assert!(errors.is_empty());
+    }
+
+    #[test]
+    fn unknown_keys() {
+        let (config, errors) = Config::parse(
+            r#"{"consol": false, "events": {"sampel_rate": 4, "sample_rate": 2},
+                "offsets": {"process_event": {"address": "0x1000"}, "gmaloc": null}}"#,
+        );
+
+        let keys: Vec<String> = errors
+            .into_iter()
+            .map(|err| match err {
+                ConfigError::UnknownKey(key) => key,
+                err => panic!("unexpected error {}", err),
+            })
+            .collect();
+        assert_eq!(keys, ["consol", "events.sampel_rate", "offsets.gmaloc"]);
+
+        // Rest of the config is still loaded
+        assert_eq!(config.events.sample_rate, 2);
+        assert!(config.offsets.process_event.is_some());
+    }
+
+    #[test]
+    fn invalid_values() {
+        let (config, errors) = Config::parse(
+            r#"{
+                "events": {"path": " ", "sample_rate": 0, "buffer_capacity": 0},
+                "offsets": {
+                    "process_event": {"address": "0x0"},
+                    "game_objects": {"pattern": "8B ZZ"},
+                    "game_names": {"address": "0x1234"}
+                },
+                "messages": {"prefix": "", "ack_url": "https://example.com", "queue_capacity": 0}
+            }"#,
+        );
+
+        let errors: Vec<String> = errors.iter().map(ToString::to_string).collect();
+        assert_eq!(
+            errors,
+            [
+                "offsets.process_event: invalid address \"0x0\"",
+                "offsets.game_objects: invalid pattern token \"ZZ\"",
+                "events.path: must not be empty",
+                "events.sample_rate: must be greater than zero",
+                "events.buffer_capacity: must be greater than zero",
+                "messages.prefix: must not be empty",
+                "messages.ack_url: invalid http URL \"https://example.com\"",
+                "messages.queue_capacity: must be greater than zero",
+            ]
+        );
+
+        // Invalid values are replaced with the defaults
+        assert!(config.offsets.process_event.is_none());
+        assert!(config.offsets.game_objects.is_none());
+        assert!(config.offsets.game_names.is_some());
+        assert_eq!(config.events.path, EventsConfig::default().path);
+        assert_eq!(config.events.sample_rate, 1);
+        assert_eq!(config.events.buffer_capacity, 8192);
+        assert_eq!(config.messages.prefix, MessagesConfig::default().prefix);
+        assert_eq!(config.messages.ack_url, None);
+        assert_eq!(config.messages.queue_capacity, 32);
+    }
+
+    #[test]
+    fn parse_errors() {
+        for value in [
+            "{",
+            "not json",
+            r#"{"log_level": "verbose"}"#,
+            r#"{"events": {"sample_rate": -1}}"#,
+            r#"{"console": "yes"}"#,
+        ] {
+            let (config, errors) = Config::parse(value);
+            assert!(
+                matches!(errors[..], [ConfigError::Parse(_)]),
+                "{}: {:?}",
+                value,
+                errors
+            );
+            assert_eq!(config.log_level, LogLevel::Info);
+        }
+    }
+
+    #[test]
+    fn addresses() {
+        assert_eq!(parse_address("0x00453120"), Some(0x00453120));
+        assert_eq!(parse_address("0X1AB5634"), Some(0x01AB5634));
+        assert_eq!(parse_address("453120"), Some(0x00453120));
+        assert_eq!(parse_address("0xZZ"), None);
+        assert_eq!(parse_address(""), None);
+    }
+}
@@ -0,0 +1,184 @@
+//! Registry of handlers for processed events, handlers are registered
+//! against the full name of a function and dispatched by the function
+//! object pointer
+
+use crate::sdk::{
+    core::{UFunction, UObject},
+    params::UFunctionParams,
+};
+use parking_lot::Mutex;
+use std::{collections::HashMap, os::raw::c_void, sync::Arc, sync::OnceLock};
+
+/// Global event handler registry
+static EVENTS: OnceLock<Mutex<EventRegistry>> = OnceLock::new();
+
+/// Gets the global event handler registry
+pub fn events() -> &'static Mutex<EventRegistry> {
+    EVENTS.get_or_init(Default::default)
+}
+
+/// Action to take after a pre handler has run
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EventAction {
+    /// Continue to the next handler and the original function
+    Continue,
+    /// Don't call the original function or any post handlers
+    Suppress,
+}
+
+/// Details about the event being processed
+pub struct EventContext {
+    /// Object the event is being processed on
+    pub object: *mut UObject,
+    /// Function being called
+    pub function: *mut UFunction,
+    /// Parameters block for the function
+    pub params: *mut c_void,
+    /// Result for the function
+    pub result: *mut c_void,
+}
+
+impl EventContext {
+    /// Gets the object the event is processed on as the provided type
+    ///
+    /// # Safety
+    ///
+    /// `T` must be the class of the object or one of its super classes
+    pub unsafe fn object<T>(&mut self) -> Option<&mut T> {
+        self.object.cast::<T>().as_mut()
+    }
+}
+
+/// Handler called before the original function, can modify the
+/// params and suppress the original call
+pub type PreHandler = unsafe fn(&mut EventContext) -> EventAction;
+
+/// Handler called after the original function has been called
+pub type PostHandler = unsafe fn(&EventContext);
+
+/// Handler called before the original function with the typed params
+pub type TypedPreHandler<P> = unsafe fn(&mut P, &mut EventContext) -> EventAction;
+
+/// Handler called after the original function with the typed params,
+/// the params contain the return value and out params
+pub type TypedPostHandler<P> = unsafe fn(&mut P, &EventContext);
+
+/// Stored pre handler, typed handlers are wrapped to decode their params
+type BoxedPreHandler = Arc<dyn Fn(&mut EventContext) -> EventAction + Send + Sync>;
+
+/// Stored post handler, typed handlers are wrapped to decode their params
+type BoxedPostHandler = Arc<dyn Fn(&EventContext) + Send + Sync>;
+
+/// Handlers registered for a single function
+#[derive(Default, Clone)]
+pub struct EventHandlers {
+    pre: Vec<BoxedPreHandler>,
+    post: Vec<BoxedPostHandler>,
+}
+
+impl EventHandlers {
+    /// Runs the pre handlers, stops at the first handler that suppresses
+    /// the event
+    ///
+    /// # Safety
+    ///
+    /// Handlers access the raw pointers of the context
+    pub unsafe fn run_pre(&self, context: &mut EventContext) -> EventAction {
+        for handler in &self.pre {
+            if handler(context) == EventAction::Suppress {
+                return EventAction::Suppress;
+            }
+        }
+        EventAction::Continue
+    }
+
+    /// Runs the post handlers
+    ///
+    /// # Safety
+    ///
+    /// Handlers access the raw pointers of the context
+    pub unsafe fn run_post(&self, context: &EventContext) {
+        for handler in &self.post {
+            handler(context);
+        }
+    }
+}
+
+#[derive(Default)]
+pub struct EventRegistry {
+    /// Handlers keyed by the function full name
+    named: HashMap<String, Arc<EventHandlers>>,
+    /// Handlers for each function object that has been seen, [None]
+    /// when the function has no handlers
+    resolved: HashMap<usize, Option<Arc<EventHandlers>>>,
+}
+
+impl EventRegistry {
+    fn handlers_mut(&mut self, name: &str) -> &mut EventHandlers {
+        // Function objects must be resolved again to pick up the new handlers
+        self.resolved.clear();
+
+        // Handlers may still be in use by an event so they are copied on write
+        Arc::make_mut(self.named.entry(name.to_string()).or_default())
+    }
+
+    /// Registers a handler to run before the function with the provided
+    /// full name (e.g. "Function SFXGame.SFXOnlineComponentUI.OnDisplayNotification")
+    pub fn register_pre(&mut self, name: &str, handler: PreHandler) {
+        self.handlers_mut(name)
+            .pre
+            .push(Arc::new(move |context| unsafe { handler(context) }));
+    }
+
+    /// Registers a handler to run after the function with the provided full name
+    pub fn register_post(&mut self, name: &str, handler: PostHandler) {
+        self.handlers_mut(name)
+            .post
+            .push(Arc::new(move |context| unsafe { handler(context) }));
+    }
+
+    /// Registers a handler to run before the function the params belong
+    /// to, the handler is skipped if the params block is null
+    pub fn on_pre<P: UFunctionParams + 'static>(&mut self, handler: TypedPreHandler<P>) {
+        self.handlers_mut(P::FUNCTION)
+            .pre
+            .push(Arc::new(move |context| unsafe {
+                match context.params.cast::<P>().as_mut() {
+                    Some(params) => handler(params, context),
+                    None => EventAction::Continue,
+                }
+            }));
+    }
+
+    /// Registers a handler to run after the function the params belong
+    /// to, the handler is skipped if the params block is null
+    pub fn on_post<P: UFunctionParams + 'static>(&mut self, handler: TypedPostHandler<P>) {
+        self.handlers_mut(P::FUNCTION)
+            .post
+            .push(Arc::new(move |context| unsafe {
+                if let Some(params) = context.params.cast::<P>().as_mut() {
+                    handler(params, context);
+                }
+            }));
+    }
+
+    /// Gets the handlers for the function, the function full name is only
+    /// looked up the first time the function is seen
+    ///
+    /// # Safety
+    ///
+    /// `function` must be a valid function object
+    pub unsafe fn get(&mut self, function: *mut UFunction) -> Option<Arc<EventHandlers>> {
+        if self.named.is_empty() {
+            return None;
+        }
+
+        self.resolved
+            .entry(function as usize)
+            .or_insert_with(|| {
+                let name = function.read().as_object_ref().get_full_name();
+                self.named.get(&name).cloned()
+            })
+            .clone()
+    }
+}
@@ -0,0 +1,70 @@
+//! Panic boundary for hook entrypoints, panics must not unwind across the
+//! FFI boundary into the game so they are caught and logged here
+
+use crate::{error, warn};
+use std::{
+    panic::{catch_unwind, AssertUnwindSafe, PanicInfo},
+    sync::atomic::{AtomicBool, AtomicU32, Ordering},
+};
+
+/// Number of panics caught at hook boundaries
+static PANIC_COUNT: AtomicU32 = AtomicU32::new(0);
+
+/// Number of panics before safe mode is enabled, zero never enables safe mode
+static PANIC_LIMIT: AtomicU32 = AtomicU32::new(0);
+
+/// Whether custom handling has been disabled after repeated panics
+static SAFE_MODE: AtomicBool = AtomicBool::new(false);
+
+/// Installs a panic hook that logs the panic message and location and
+/// sets the number of panics allowed before safe mode is enabled
+pub fn init(panic_limit: u32) {
+    PANIC_LIMIT.store(panic_limit, Ordering::Relaxed);
+    std::panic::set_hook(Box::new(log_panic));
+}
+
+fn log_panic(info: &PanicInfo<'_>) {
+    let message = if let Some(value) = info.payload().downcast_ref::<&str>() {
+        value
+    } else if let Some(value) = info.payload().downcast_ref::<String>() {
+        value.as_str()
+    } else {
+        "Unknown panic"
+    };
+
+    match info.location() {
+        Some(location) => error!(
+            "Panic: {} ({}:{}:{})",
+            message,
+            location.file(),
+            location.line(),
+            location.column()
+        ),
+        None => error!("Panic: {}", message),
+    }
+}
+
+/// Whether custom handling has been disabled after repeated panics, hooks
+/// should only call the original function while in safe mode
+pub fn is_safe_mode() -> bool {
+    SAFE_MODE.load(Ordering::Relaxed)
+}
+
+/// Runs the provided function catching any panics, returns [None]
+/// if the function panicked. The panic is logged by the panic hook.
+pub fn catch<R>(name: &str, f: impl FnOnce() -> R) -> Option<R> {
+    match catch_unwind(AssertUnwindSafe(f)) {
+        Ok(value) => Some(value),
+        Err(_) => {
+            let count = PANIC_COUNT.fetch_add(1, Ordering::Relaxed) + 1;
+            error!("Caught panic in {} hook ({} total)", name, count);
+
+            let limit = PANIC_LIMIT.load(Ordering::Relaxed);
+            if limit != 0 && count >= limit && !SAFE_MODE.swap(true, Ordering::Relaxed) {
+                warn!("Too many panics, entering safe mode and disabling custom handling");
+            }
+
+            None
+        }
+    }
+}
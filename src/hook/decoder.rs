@@ -0,0 +1,777 @@
+//! Minimal x86-32 length disassembler used to safely steal the prologue
+//! bytes of a function when installing a hook.
+//!
+//! This doesn't attempt to fully disassemble instructions, it only determines
+//! the length of each instruction and whether it contains a relative branch
+//! displacement that must be adjusted once the instruction is moved into
+//! a trampoline.
+
+use std::fmt::Display;
+
+/// Maximum length of a single x86 instruction
+pub const MAX_INSTRUCTION_SIZE: usize = 15;
+
+/// Kind of relative branch present within an instruction
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BranchKind {
+    /// Relative call (E8)
+    Call,
+    /// Unconditional relative jump (E9, EB)
+    Jump,
+    /// Conditional relative jump (70-7F, 0F 80-8F)
+    Conditional,
+    /// Loop and JECXZ instructions (E0-E3), only have a rel8 form
+    Loop,
+}
+
+/// Relative branch displacement within an instruction
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Branch {
+    /// The kind of branch
+    pub kind: BranchKind,
+    /// Offset of the opcode from the start of the instruction (After prefixes)
+    pub opcode_offset: usize,
+    /// Offset of the displacement from the start of the instruction
+    pub offset: usize,
+    /// Size of the displacement in bytes (1 or 4)
+    pub size: usize,
+}
+
+/// Decoded instruction details
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Instruction {
+    /// Total length of the instruction in bytes
+    pub length: usize,
+    /// Relative branch details if the instruction is a relative branch
+    pub branch: Option<Branch>,
+    /// Whether execution never falls through to the next instruction
+    /// (ret, jmp, int3)
+    pub terminal: bool,
+}
+
+/// Errors that can occur while decoding or relocating instructions
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DecodeError {
+    /// Ran out of bytes before the instruction was complete
+    Truncated,
+    /// Instruction was longer than the maximum instruction size
+    TooLong,
+    /// Opcode that isn't known to the decoder
+    UnknownOpcode(u8),
+    /// Relative branch that can't be moved (rel16, loop, or a branch
+    /// back into the stolen bytes)
+    UnsupportedBranch,
+    /// Function ends before enough bytes could be stolen
+    FunctionTooShort,
+}
+
+impl Display for DecodeError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            DecodeError::Truncated => f.write_str("instruction truncated"),
+            DecodeError::TooLong => f.write_str("instruction exceeds maximum length"),
+            DecodeError::UnknownOpcode(opcode) => write!(f, "unknown opcode {:#04X}", opcode),
+            DecodeError::UnsupportedBranch => f.write_str("relative branch cannot be relocated"),
+            DecodeError::FunctionTooShort => f.write_str("function too short to hook"),
+        }
+    }
+}
+
+impl std::error::Error for DecodeError {}
+
+/// Immediate operand size for an opcode
+#[derive(Clone, Copy)]
+enum Immediate {
+    None,
+    /// Fixed size immediate
+    Fixed(usize),
+    /// 4 byte immediate, 2 bytes with an operand size prefix
+    Operand,
+    /// 4 byte address, 2 bytes with an address size prefix (moffs)
+    Address,
+    /// Far pointer, 6 bytes, 4 bytes with an operand size prefix
+    FarPointer,
+}
+
+/// Reads the next byte from the code at the provided cursor
+fn next(code: &[u8], cursor: &mut usize) -> Result<u8, DecodeError> {
+    let value = *code.get(*cursor).ok_or(DecodeError::Truncated)?;
+    *cursor += 1;
+    Ok(value)
+}
+
+/// Determines the length of the ModRM byte and any following SIB
+/// and displacement bytes, the cursor should be positioned at the ModRM byte
+fn modrm_length(code: &[u8], cursor: usize, address_16: bool) -> Result<usize, DecodeError> {
+    let modrm = *code.get(cursor).ok_or(DecodeError::Truncated)?;
+    let mode = modrm >> 6;
+    let rm = modrm & 0b111;
+
+    if mode == 0b11 {
+        return Ok(1);
+    }
+
+    if address_16 {
+        let displacement = match mode {
+            0b00 if rm == 0b110 => 2,
+            0b00 => 0,
+            0b01 => 1,
+            _ => 2,
+        };
+        return Ok(1 + displacement);
+    }
+
+    let mut length = 1;
+
+    if rm == 0b100 {
+        // SIB byte follows
+        let sib = *code.get(cursor + 1).ok_or(DecodeError::Truncated)?;
+        length += 1;
+
+        if mode == 0b00 && sib & 0b111 == 0b101 {
+            return Ok(length + 4);
+        }
+    }
+
+    let displacement = match mode {
+        0b00 if rm == 0b101 => 4,
+        0b00 => 0,
+        0b01 => 1,
+        _ => 4,
+    };
+
+    Ok(length + displacement)
+}
+
+/// Decodes the length and branch details of the instruction at the
+/// start of the provided code
+pub fn decode(code: &[u8]) -> Result<Instruction, DecodeError> {
+    let mut cursor = 0;
+    let mut operand_16 = false;
+    let mut address_16 = false;
+
+    // Consume prefixes
+    let opcode = loop {
+        let value = next(code, &mut cursor)?;
+        match value {
+            0x66 => operand_16 = true,
+            0x67 => address_16 = true,
+            0xF0 | 0xF2 | 0xF3 | 0x26 | 0x2E | 0x36 | 0x3E | 0x64 | 0x65 => {}
+            value => break value,
+        }
+
+        if cursor >= MAX_INSTRUCTION_SIZE {
+            return Err(DecodeError::TooLong);
+        }
+    };
+
+    let opcode_offset = cursor - 1;
+    let mut has_modrm = false;
+    let mut immediate = Immediate::None;
+    let mut branch: Option<(BranchKind, usize)> = None;
+    let mut terminal = false;
+
+    if opcode == 0x0F {
+        let opcode = next(code, &mut cursor)?;
+        match opcode {
+            // Three byte opcodes
+            0x38 => {
+                next(code, &mut cursor)?;
+                has_modrm = true;
+            }
+            0x3A => {
+                next(code, &mut cursor)?;
+                has_modrm = true;
+                immediate = Immediate::Fixed(1);
+            }
+
+            // Jcc rel32
+            0x80..=0x8F => branch = Some((BranchKind::Conditional, 4)),
+
+            // UD2
+            0x0B => terminal = true,
+
+            // Single byte instructions
+            0x05..=0x09 | 0x0E | 0x30..=0x37 | 0x77 | 0xA0..=0xA2 | 0xA8..=0xAA | 0xC8..=0xCF => {}
+
+            // ModRM with an imm8
+            0x0F | 0x70..=0x73 | 0xA4 | 0xAC | 0xBA | 0xC2 | 0xC4..=0xC6 => {
+                has_modrm = true;
+                immediate = Immediate::Fixed(1);
+            }
+
+            // ModRM only
+            0x00..=0x03
+            | 0x0D
+            | 0x10..=0x2F
+            | 0x40..=0x6F
+            | 0x74..=0x76
+            | 0x78..=0x7F
+            | 0x90..=0x9F
+            | 0xA3
+            | 0xA5
+            | 0xAB
+            | 0xAD..=0xB9
+            | 0xBB..=0xC1
+            | 0xC3
+            | 0xC7
+            | 0xD0..=0xFF => has_modrm = true,
+
+            opcode => return Err(DecodeError::UnknownOpcode(opcode)),
+        }
+    } else {
+        match opcode {
+            // ALU operations with ModRM
+            0x00..=0x03
+            | 0x08..=0x0B
+            | 0x10..=0x13
+            | 0x18..=0x1B
+            | 0x20..=0x23
+            | 0x28..=0x2B
+            | 0x30..=0x33
+            | 0x38..=0x3B => has_modrm = true,
+
+            // ALU operations on AL with imm8
+            0x04 | 0x0C | 0x14 | 0x1C | 0x24 | 0x2C | 0x34 | 0x3C => {
+                immediate = Immediate::Fixed(1)
+            }
+
+            // ALU operations on EAX with imm32
+            0x05 | 0x0D | 0x15 | 0x1D | 0x25 | 0x2D | 0x35 | 0x3D => immediate = Immediate::Operand,
+
+            // Segment push/pop, BCD adjust, inc/dec/push/pop reg, pusha/popa
+            0x06
+            | 0x07
+            | 0x0E
+            | 0x16
+            | 0x17
+            | 0x1E
+            | 0x1F
+            | 0x27
+            | 0x2F
+            | 0x37
+            | 0x3F
+            | 0x40..=0x61 => {}
+
+            // BOUND, ARPL
+            0x62 | 0x63 => has_modrm = true,
+
+            // PUSH imm32
+            0x68 => immediate = Immediate::Operand,
+            // IMUL r, r/m, imm32
+            0x69 => {
+                has_modrm = true;
+                immediate = Immediate::Operand;
+            }
+            // PUSH imm8
+            0x6A => immediate = Immediate::Fixed(1),
+            // IMUL r, r/m, imm8
+            0x6B => {
+                has_modrm = true;
+                immediate = Immediate::Fixed(1);
+            }
+            // INS/OUTS
+            0x6C..=0x6F => {}
+
+            // Jcc rel8
+            0x70..=0x7F => branch = Some((BranchKind::Conditional, 1)),
+
+            // Group 1 with imm8
+            0x80 | 0x82 | 0x83 => {
+                has_modrm = true;
+                immediate = Immediate::Fixed(1);
+            }
+            // Group 1 with imm32
+            0x81 => {
+                has_modrm = true;
+                immediate = Immediate::Operand;
+            }
+
+            // TEST, XCHG, MOV, LEA, POP r/m
+            0x84..=0x8F => has_modrm = true,
+
+            // NOP, XCHG, CWDE, CDQ, FWAIT, PUSHF, POPF, SAHF, LAHF
+            0x90..=0x99 | 0x9B..=0x9F => {}
+
+            // CALL far ptr16:32
+            0x9A => immediate = Immediate::FarPointer,
+
+            // MOV AL/EAX <-> moffs
+            0xA0..=0xA3 => immediate = Immediate::Address,
+
+            // String operations
+            0xA4..=0xA7 | 0xAA..=0xAF => {}
+
+            // TEST AL, imm8
+            0xA8 => immediate = Immediate::Fixed(1),
+            // TEST EAX, imm32
+            0xA9 => immediate = Immediate::Operand,
+
+            // MOV r8, imm8
+            0xB0..=0xB7 => immediate = Immediate::Fixed(1),
+            // MOV r32, imm32
+            0xB8..=0xBF => immediate = Immediate::Operand,
+
+            // Shift group with imm8
+            0xC0 | 0xC1 => {
+                has_modrm = true;
+                immediate = Immediate::Fixed(1);
+            }
+
+            // RET imm16
+            0xC2 | 0xCA => {
+                immediate = Immediate::Fixed(2);
+                terminal = true;
+            }
+            // RET
+            0xC3 | 0xCB | 0xCF => terminal = true,
+
+            // LES, LDS
+            0xC4 | 0xC5 => has_modrm = true,
+
+            // MOV r/m8, imm8
+            0xC6 => {
+                has_modrm = true;
+                immediate = Immediate::Fixed(1);
+            }
+            // MOV r/m32, imm32
+            0xC7 => {
+                has_modrm = true;
+                immediate = Immediate::Operand;
+            }
+
+            // ENTER imm16, imm8
+            0xC8 => immediate = Immediate::Fixed(3),
+            // LEAVE
+            0xC9 => {}
+            // INT3
+            0xCC => terminal = true,
+            // INT imm8
+            0xCD => immediate = Immediate::Fixed(1),
+            // INTO
+            0xCE => {}
+
+            // Shift group, FPU instructions
+            0xD0..=0xD3 | 0xD8..=0xDF => has_modrm = true,
+
+            // AAM, AAD
+            0xD4 | 0xD5 => immediate = Immediate::Fixed(1),
+            // SALC, XLAT
+            0xD6 | 0xD7 => {}
+
+            // LOOPNE, LOOPE, LOOP, JECXZ
+            0xE0..=0xE3 => branch = Some((BranchKind::Loop, 1)),
+
+            // IN/OUT imm8
+            0xE4..=0xE7 => immediate = Immediate::Fixed(1),
+
+            // CALL rel32
+            0xE8 => branch = Some((BranchKind::Call, 4)),
+            // JMP rel32
+            0xE9 => {
+                branch = Some((BranchKind::Jump, 4));
+                terminal = true;
+            }
+            // JMP far ptr16:32
+            0xEA => {
+                immediate = Immediate::FarPointer;
+                terminal = true;
+            }
+            // JMP rel8
+            0xEB => {
+                branch = Some((BranchKind::Jump, 1));
+                terminal = true;
+            }
+
+            // IN/OUT DX, INT1, HLT, CMC, flag operations
+            0xEC..=0xEF | 0xF1 | 0xF4 | 0xF5 | 0xF8..=0xFD => {}
+
+            // Group 3, TEST has an immediate
+            0xF6 | 0xF7 => {
+                has_modrm = true;
+                let modrm = *code.get(cursor).ok_or(DecodeError::Truncated)?;
+                if (modrm >> 3) & 0b111 < 2 {
+                    immediate = if opcode == 0xF6 {
+                        Immediate::Fixed(1)
+                    } else {
+                        Immediate::Operand
+                    };
+                }
+            }
+
+            // Group 4/5 (INC, DEC, CALL, JMP, PUSH r/m)
+            0xFE => has_modrm = true,
+            0xFF => {
+                has_modrm = true;
+                let modrm = *code.get(cursor).ok_or(DecodeError::Truncated)?;
+                // Indirect JMP
+                if matches!((modrm >> 3) & 0b111, 4 | 5) {
+                    terminal = true;
+                }
+            }
+
+            opcode => return Err(DecodeError::UnknownOpcode(opcode)),
+        }
+    }
+
+    if has_modrm {
+        cursor += modrm_length(code, cursor, address_16)?;
+    }
+
+    cursor += match immediate {
+        Immediate::None => 0,
+        Immediate::Fixed(size) => size,
+        Immediate::Operand if operand_16 => 2,
+        Immediate::Operand => 4,
+        Immediate::Address if address_16 => 2,
+        Immediate::Address => 4,
+        Immediate::FarPointer if operand_16 => 4,
+        Immediate::FarPointer => 6,
+    };
+
+    let branch = match branch {
+        Some((kind, size)) => {
+            // Operand size prefix on rel32 branches changes them to rel16
+            if size == 4 && operand_16 {
+                return Err(DecodeError::UnsupportedBranch);
+            }
+
+            let offset = cursor;
+            cursor += size;
+            Some(Branch {
+                kind,
+                opcode_offset,
+                offset,
+                size,
+            })
+        }
+        None => None,
+    };
+
+    if cursor > MAX_INSTRUCTION_SIZE {
+        return Err(DecodeError::TooLong);
+    }
+
+    if cursor > code.len() {
+        return Err(DecodeError::Truncated);
+    }
+
+    Ok(Instruction {
+        length: cursor,
+        branch,
+        terminal,
+    })
+}
+
+/// Instructions relocated from a function prologue
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Relocated {
+    /// Relocated instruction bytes to place at the destination
+    pub code: Vec<u8>,
+    /// Number of whole instruction bytes taken from the source
+    pub stolen: usize,
+}
+
+/// Copies whole instructions from the start of `code` until at least
+/// `min_length` bytes have been taken, adjusting any relative branches so
+/// they remain valid when executed from `destination`.
+///
+/// `source` is the address the code was read from, branches using rel8
+/// displacements are widened to their rel32 forms.
+pub fn relocate(
+    code: &[u8],
+    source: usize,
+    destination: usize,
+    min_length: usize,
+) -> Result<Relocated, DecodeError> {
+    let mut out = Vec::with_capacity(min_length + MAX_INSTRUCTION_SIZE);
+    let mut stolen = 0;
+    let mut targets = Vec::new();
+
+    while stolen < min_length {
+        let instruction = decode(&code[stolen..])?;
+        let bytes = &code[stolen..stolen + instruction.length];
+        let end = stolen + instruction.length;
+
+        // Instruction wont fall through so the function ends
+        // before enough bytes were taken
+        if instruction.terminal && end < min_length {
+            return Err(DecodeError::FunctionTooShort);
+        }
+
+        let branch = match instruction.branch {
+            Some(value) => value,
+            None => {
+                out.extend_from_slice(bytes);
+                stolen = end;
+                continue;
+            }
+        };
+
+        let displacement = match branch.size {
+            1 => bytes[branch.offset] as i8 as isize,
+            _ => i32::from_le_bytes([
+                bytes[branch.offset],
+                bytes[branch.offset + 1],
+                bytes[branch.offset + 2],
+                bytes[branch.offset + 3],
+            ]) as isize,
+        };
+
+        // Relative offset of the branch target from the start of the source
+        let target_offset = end as isize + displacement;
+        targets.push(target_offset);
+
+        let target = source.wrapping_add_signed(target_offset);
+
+        // Prefixes are preserved, the opcode is replaced with the rel32 form
+        out.extend_from_slice(&bytes[..branch.opcode_offset]);
+
+        match (branch.kind, branch.size) {
+            (BranchKind::Loop, _) => return Err(DecodeError::UnsupportedBranch),
+            (BranchKind::Jump, 1) => out.push(0xE9),
+            (BranchKind::Conditional, 1) => {
+                out.push(0x0F);
+                out.push(0x80 | (bytes[branch.opcode_offset] & 0x0F));
+            }
+            _ => out.extend_from_slice(&bytes[branch.opcode_offset..branch.offset]),
+        }
+
+        let next_address = destination.wrapping_add(out.len() + 4);
+        let new_displacement = target.wrapping_sub(next_address) as u32;
+        out.extend_from_slice(&new_displacement.to_le_bytes());
+
+        stolen = end;
+    }
+
+    // Branches into the middle of the stolen bytes would land on the hook jump
+    if targets
+        .iter()
+        .any(|target| *target > 0 && *target < stolen as isize)
+    {
+        return Err(DecodeError::UnsupportedBranch);
+    }
+
+    Ok(Relocated { code: out, stolen })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Instruction lengths for known byte sequences
+    #[test]
+    fn decode_lengths() {
+        let cases: &[(&[u8], usize)] = &[
+            // mov eax, [ebp+8]
+            (&[0x8B, 0x45, 0x08], 3),
+            // mov eax, [ecx+esi*4]
+            (&[0x8B, 0x04, 0xB1], 3),
+            // mov eax, [disp32+esi*0] (SIB without a base)
+            (&[0x8B, 0x04, 0x25, 0x78, 0x56, 0x34, 0x12], 7),
+            // mov ecx, [disp32]
+            (&[0x8B, 0x0D, 0x78, 0x56, 0x34, 0x12], 6),
+            // mov eax, [esp+disp32]
+            (&[0x8B, 0x84, 0x24, 0x00, 0x01, 0x00, 0x00], 7),
+            // mov eax, [esp+disp8]
+            (&[0x8B, 0x44, 0x24, 0x04], 4),
+            // mov ax, imm16
+            (&[0x66, 0xB8, 0x34, 0x12], 4),
+            // add ax, imm16
+            (&[0x66, 0x05, 0x34, 0x12], 4),
+            // mov eax, [bp+disp8] (16bit addressing)
+            (&[0x67, 0x8B, 0x46, 0x08], 4),
+            // mov eax, [disp16] (16bit addressing)
+            (&[0x67, 0x8B, 0x06, 0x34, 0x12], 5),
+            // test cl, imm8
+            (&[0xF6, 0xC1, 0x01], 3),
+            // not cl
+            (&[0xF6, 0xD1], 2),
+            // test ecx, imm32
+            (&[0xF7, 0xC1, 0x78, 0x56, 0x34, 0x12], 6),
+            // test cx, imm16
+            (&[0x66, 0xF7, 0xC1, 0x34, 0x12], 5),
+            // neg eax
+            (&[0xF7, 0xD8], 2),
+            // nop dword [eax+eax*1+0]
+            (&[0x0F, 0x1F, 0x44, 0x00, 0x00], 5),
+            // nop dword [eax+0]
+            (&[0x0F, 0x1F, 0x80, 0x00, 0x00, 0x00, 0x00], 7),
+            // nop word [eax+eax*1+0]
+            (&[0x66, 0x0F, 0x1F, 0x44, 0x00, 0x00], 6),
+            // mov eax, moffs32
+            (&[0xA1, 0x78, 0x56, 0x34, 0x12], 5),
+            // mov eax, moffs16
+            (&[0x67, 0xA1, 0x34, 0x12], 4),
+            // push ebp
+            (&[0x55], 1),
+            // sub esp, imm8
+            (&[0x83, 0xEC, 0x10], 3),
+            // push imm32
+            (&[0x68, 0x78, 0x56, 0x34, 0x12], 5),
+            // mov fs:[0], esp
+            (&[0x64, 0x89, 0x25, 0x00, 0x00, 0x00, 0x00], 7),
+        ];
+
+        for (code, length) in cases {
+            let instruction = decode(code).unwrap();
+            assert_eq!(instruction.length, *length, "{:02X?}", code);
+            assert_eq!(instruction.branch, None, "{:02X?}", code);
+        }
+    }
+
+    #[test]
+    fn decode_branches() {
+        let call = decode(&[0xE8, 0x00, 0x01, 0x00, 0x00]).unwrap();
+        assert_eq!(call.length, 5);
+        assert!(!call.terminal);
+        assert_eq!(
+            call.branch,
+            Some(Branch {
+                kind: BranchKind::Call,
+                opcode_offset: 0,
+                offset: 1,
+                size: 4
+            })
+        );
+
+        let jcc = decode(&[0x0F, 0x84, 0x00, 0x01, 0x00, 0x00]).unwrap();
+        assert_eq!(jcc.length, 6);
+        assert_eq!(jcc.branch.unwrap().kind, BranchKind::Conditional);
+        assert_eq!(jcc.branch.unwrap().offset, 2);
+
+        let jmp = decode(&[0xEB, 0x10]).unwrap();
+        assert_eq!(jmp.length, 2);
+        assert!(jmp.terminal);
+        assert_eq!(jmp.branch.unwrap().size, 1);
+
+        // Prefixes come before the opcode
+        let prefixed = decode(&[0x3E, 0x74, 0x10]).unwrap();
+        assert_eq!(prefixed.branch.unwrap().opcode_offset, 1);
+        assert_eq!(prefixed.branch.unwrap().offset, 2);
+    }
+
+    #[test]
+    fn decode_terminal() {
+        assert!(decode(&[0xC3]).unwrap().terminal);
+        assert!(decode(&[0xC2, 0x04, 0x00]).unwrap().terminal);
+        assert!(decode(&[0xCC]).unwrap().terminal);
+        // jmp [disp32]
+        let jmp = decode(&[0xFF, 0x25, 0x78, 0x56, 0x34, 0x12]).unwrap();
+        assert_eq!(jmp.length, 6);
+        assert!(jmp.terminal);
+        // call [disp32]
+        assert!(
+            !decode(&[0xFF, 0x15, 0x78, 0x56, 0x34, 0x12])
+                .unwrap()
+                .terminal
+        );
+    }
+
+    #[test]
+    fn decode_errors() {
+        assert_eq!(decode(&[]), Err(DecodeError::Truncated));
+        assert_eq!(decode(&[0x8B]), Err(DecodeError::Truncated));
+        assert_eq!(decode(&[0x8B, 0x0D, 0x78]), Err(DecodeError::Truncated));
+        assert_eq!(decode(&[0xE8, 0x00, 0x00]), Err(DecodeError::Truncated));
+        assert_eq!(decode(&[0x0F, 0x04]), Err(DecodeError::UnknownOpcode(0x04)));
+        // jmp rel16
+        assert_eq!(
+            decode(&[0x66, 0xE9, 0x00, 0x01]),
+            Err(DecodeError::UnsupportedBranch)
+        );
+        assert_eq!(decode(&[0x66; 16]), Err(DecodeError::TooLong));
+    }
+
+    #[test]
+    fn relocate_plain() {
+        // push ebp; mov ebp, esp; sub esp, 0x10
+        let code = [0x55, 0x8B, 0xEC, 0x83, 0xEC, 0x10, 0xC3];
+        let relocated = relocate(&code, 0x1000, 0x2000, 5).unwrap();
+        assert_eq!(relocated.stolen, 6);
+        assert_eq!(relocated.code, &code[..6]);
+    }
+
+    #[test]
+    fn relocate_widens_jcc() {
+        // je +0x10; nop x3
+        let code = [0x74, 0x10, 0x90, 0x90, 0x90, 0x90];
+        let relocated = relocate(&code, 0x1000, 0x2000, 5).unwrap();
+        assert_eq!(relocated.stolen, 5);
+
+        // Target 0x1012 from the end of the 6 byte jcc at 0x2000
+        let displacement = (0x1012u32).wrapping_sub(0x2006);
+        let mut expected = vec![0x0F, 0x84];
+        expected.extend_from_slice(&displacement.to_le_bytes());
+        expected.extend_from_slice(&[0x90, 0x90, 0x90]);
+        assert_eq!(relocated.code, expected);
+    }
+
+    #[test]
+    fn relocate_widens_jmp() {
+        // jmp +0x20 ends the function so it must be the last stolen instruction
+        let code = [0x90, 0x90, 0x90, 0xEB, 0x20];
+        let relocated = relocate(&code, 0x1000, 0x2000, 5).unwrap();
+
+        let displacement = (0x1025u32).wrapping_sub(0x2008);
+        let mut expected = vec![0x90, 0x90, 0x90, 0xE9];
+        expected.extend_from_slice(&displacement.to_le_bytes());
+        assert_eq!(relocated.code, expected);
+    }
+
+    #[test]
+    fn relocate_retargets_call() {
+        // call +0x100
+        let code = [0xE8, 0x00, 0x01, 0x00, 0x00];
+        let relocated = relocate(&code, 0x1000, 0x3000, 5).unwrap();
+
+        let displacement = (0x1105u32).wrapping_sub(0x3005);
+        let mut expected = vec![0xE8];
+        expected.extend_from_slice(&displacement.to_le_bytes());
+        assert_eq!(relocated.code, expected);
+        assert_eq!(relocated.stolen, 5);
+    }
+
+    #[test]
+    fn relocate_errors() {
+        let cases: &[(&[u8], DecodeError)] = &[
+            // ret before enough bytes
+            (
+                &[0xC3, 0x90, 0x90, 0x90, 0x90],
+                DecodeError::FunctionTooShort,
+            ),
+            // jmp before enough bytes
+            (
+                &[0xEB, 0x00, 0x90, 0x90, 0x90],
+                DecodeError::FunctionTooShort,
+            ),
+            // loop
+            (
+                &[0xE2, 0x00, 0x90, 0x90, 0x90],
+                DecodeError::UnsupportedBranch,
+            ),
+            // call rel16
+            (
+                &[0x66, 0xE8, 0x00, 0x01, 0x90],
+                DecodeError::UnsupportedBranch,
+            ),
+            // je back into the stolen bytes
+            (
+                &[0x90, 0x74, 0xFE, 0x90, 0x90, 0x90],
+                DecodeError::UnsupportedBranch,
+            ),
+            // Not enough bytes
+            (&[0x90, 0x90], DecodeError::Truncated),
+            (&[0x90, 0x90, 0x8B, 0x0D, 0x00], DecodeError::Truncated),
+        ];
+
+        for (code, err) in cases {
+            assert_eq!(
+                relocate(code, 0x1000, 0x2000, 5),
+                Err(*err),
+                "{:02X?}",
+                code
+            );
+        }
+    }
+}
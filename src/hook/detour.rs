@@ -0,0 +1,211 @@
+use super::decoder::{relocate, DecodeError, MAX_INSTRUCTION_SIZE};
+use std::{fmt::Display, os::raw::c_void};
+use windows_sys::Win32::System::Memory::{
+    VirtualAlloc, VirtualFree, VirtualProtect, MEM_COMMIT, MEM_RELEASE, MEM_RESERVE,
+    PAGE_EXECUTE_READWRITE,
+};
+
+const JMP_SIZE: usize = 5; // Size of a near jump instruction in x86
+
+/// Number of bytes read from the target when decoding the prologue, enough
+/// for the jump plus the longest instruction that could overlap it
+const PROLOGUE_READ_SIZE: usize = JMP_SIZE + MAX_INSTRUCTION_SIZE;
+
+/// Size of the trampoline allocation, relocated instructions can grow when
+/// short branches are widened so extra space is reserved
+const TRAMPOLINE_SIZE: usize = 64;
+
+/// Errors that can occur while creating or changing a hook
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HookError {
+    /// Target prologue couldn't be safely relocated
+    Decode(DecodeError),
+    /// Failed to allocate the trampoline memory
+    AllocationFailed,
+    /// Failed to change the memory protection of the target
+    ProtectFailed,
+    /// Target address already has a hook installed
+    AlreadyHooked(usize),
+    /// No hook is installed at the target address
+    NotHooked(usize),
+}
+
+impl Display for HookError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            HookError::Decode(err) => write!(f, "failed to decode prologue: {}", err),
+            HookError::AllocationFailed => f.write_str("failed to allocate trampoline"),
+            HookError::ProtectFailed => f.write_str("failed to change memory protection"),
+            HookError::AlreadyHooked(target) => write!(f, "{:#010X} is already hooked", target),
+            HookError::NotHooked(target) => write!(f, "{:#010X} is not hooked", target),
+        }
+    }
+}
+
+impl std::error::Error for HookError {}
+
+impl From<DecodeError> for HookError {
+    fn from(value: DecodeError) -> Self {
+        HookError::Decode(value)
+    }
+}
+
+/// Creates a near jump instruction from `from` to `to`
+fn create_jump(from: usize, to: usize) -> [u8; JMP_SIZE] {
+    let relative_offset = to.wrapping_sub(from).wrapping_sub(JMP_SIZE) as u32;
+    let offset = relative_offset.to_le_bytes();
+    [0xE9, offset[0], offset[1], offset[2], offset[3]]
+}
+
+/// Writes the provided bytes over executable memory at the target
+unsafe fn write_code(target: *mut u8, bytes: &[u8]) -> Result<(), HookError> {
+    // Change memory permissions to writable
+    let mut old_protect: u32 = 0;
+    if VirtualProtect(
+        target as *mut c_void,
+        bytes.len(),
+        PAGE_EXECUTE_READWRITE,
+        &mut old_protect,
+    ) == 0
+    {
+        return Err(HookError::ProtectFailed);
+    }
+
+    std::ptr::copy_nonoverlapping(bytes.as_ptr(), target, bytes.len());
+
+    // Restore memory permissions
+    VirtualProtect(
+        target as *mut c_void,
+        bytes.len(),
+        old_protect,
+        &mut old_protect,
+    );
+
+    Ok(())
+}
+
+/// Detour from a target function to a hook function, owns the saved
+/// original bytes and the trampoline used to call the original function
+pub struct Detour {
+    /// Address of the function that is hooked
+    target: *mut u8,
+    /// Original bytes from the target that are replaced by the patch
+    original_bytes: Vec<u8>,
+    /// Jump to the hook padded to the length of the original bytes
+    patch: Vec<u8>,
+    /// Trampoline containing the relocated original bytes
+    trampoline: *mut u8,
+    /// Whether the patch is currently written to the target
+    enabled: bool,
+}
+
+// Detours are only modified while held by the hook manager lock
+unsafe impl Send for Detour {}
+
+impl Detour {
+    /// Creates a new detour from `target` to `hook`, the trampoline is
+    /// created but the target is not patched until [Detour::enable]
+    ///
+    /// # Safety
+    ///
+    /// `target` must point to the start of a function in executable memory
+    pub unsafe fn new(target: *mut u8, hook: *const u8) -> Result<Self, HookError> {
+        let prologue = std::slice::from_raw_parts(target, PROLOGUE_READ_SIZE);
+
+        let trampoline = VirtualAlloc(
+            std::ptr::null_mut(),
+            TRAMPOLINE_SIZE,
+            MEM_COMMIT | MEM_RESERVE,
+            PAGE_EXECUTE_READWRITE,
+        );
+
+        if trampoline.is_null() {
+            return Err(HookError::AllocationFailed);
+        }
+
+        // Copy whole instructions from the prologue into the trampoline
+        let relocated = match relocate(prologue, target as usize, trampoline as usize, JMP_SIZE) {
+            Ok(value) => value,
+            Err(err) => {
+                VirtualFree(trampoline, 0, MEM_RELEASE);
+                return Err(err.into());
+            }
+        };
+
+        // Write the relocated instructions followed by a jump back to the
+        // remainder of the original function
+        let trampoline = trampoline as *mut u8;
+        let jump_back_from = trampoline.add(relocated.code.len());
+        let jump_back = create_jump(
+            jump_back_from as usize,
+            target.add(relocated.stolen) as usize,
+        );
+
+        std::ptr::copy_nonoverlapping(relocated.code.as_ptr(), trampoline, relocated.code.len());
+        std::ptr::copy_nonoverlapping(jump_back.as_ptr(), jump_back_from, JMP_SIZE);
+
+        // Construct the jump instruction to the hook function, padding any
+        // remaining stolen bytes with NOPs
+        let mut patch = vec![0x90; relocated.stolen];
+        patch[..JMP_SIZE].copy_from_slice(&create_jump(target as usize, hook as usize));
+
+        Ok(Self {
+            target,
+            original_bytes: prologue[..relocated.stolen].to_vec(),
+            patch,
+            trampoline,
+            enabled: false,
+        })
+    }
+
+    /// Address of the hooked function
+    pub fn target(&self) -> usize {
+        self.target as usize
+    }
+
+    /// Address of the trampoline that calls the original function
+    pub fn trampoline(&self) -> usize {
+        self.trampoline as usize
+    }
+
+    /// Writes the jump to the hook over the target function
+    ///
+    /// # Safety
+    ///
+    /// No thread may be executing the target prologue while it is patched
+    pub unsafe fn enable(&mut self) -> Result<(), HookError> {
+        if self.enabled {
+            return Ok(());
+        }
+
+        write_code(self.target, &self.patch)?;
+        self.enabled = true;
+        Ok(())
+    }
+
+    /// Restores the original bytes of the target function
+    ///
+    /// # Safety
+    ///
+    /// No thread may be executing the target prologue while it is restored
+    pub unsafe fn disable(&mut self) -> Result<(), HookError> {
+        if !self.enabled {
+            return Ok(());
+        }
+
+        write_code(self.target, &self.original_bytes)?;
+        self.enabled = false;
+        Ok(())
+    }
+}
+
+impl Drop for Detour {
+    fn drop(&mut self) {
+        unsafe {
+            // Trampoline can't be freed if the target still jumps to the hook
+            if self.disable().is_ok() {
+                VirtualFree(self.trampoline as *mut c_void, 0, MEM_RELEASE);
+            }
+        }
+    }
+}
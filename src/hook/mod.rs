@@ -0,0 +1,219 @@
+use self::detour::{Detour, HookError};
+use std::{
+    marker::PhantomData,
+    mem::{size_of, transmute_copy},
+    sync::atomic::{AtomicUsize, Ordering},
+    time::{Duration, Instant},
+};
+
+pub mod decoder;
+pub mod detour;
+
+/// Collection of the installed detours keyed by their target address
+pub struct HookManager {
+    detours: Vec<Detour>,
+}
+
+impl HookManager {
+    pub const fn new() -> Self {
+        Self {
+            detours: Vec::new(),
+        }
+    }
+
+    /// Creates a detour from `target` to `hook` returning a function pointer
+    /// that can be used to call the original function. The detour starts
+    /// disabled so the original can be stored before [HookManager::enable]
+    ///
+    /// # Safety
+    ///
+    /// `target` must point to the start of a function with the same signature
+    /// as `F`, `F` must be a function pointer type
+    pub unsafe fn install<F: Copy>(&mut self, target: usize, hook: F) -> Result<F, HookError> {
+        assert_eq!(
+            size_of::<F>(),
+            size_of::<usize>(),
+            "Hook type must be a function pointer"
+        );
+
+        if self.find(target).is_some() {
+            return Err(HookError::AlreadyHooked(target));
+        }
+
+        let hook: usize = transmute_copy(&hook);
+        let detour = Detour::new(target as *mut u8, hook as *const u8)?;
+        let original: F = transmute_copy(&detour.trampoline());
+
+        self.detours.push(detour);
+
+        Ok(original)
+    }
+
+    fn find(&self, target: usize) -> Option<usize> {
+        self.detours
+            .iter()
+            .position(|detour| detour.target() == target)
+    }
+
+    fn get_mut(&mut self, target: usize) -> Result<&mut Detour, HookError> {
+        let index = self.find(target).ok_or(HookError::NotHooked(target))?;
+        Ok(&mut self.detours[index])
+    }
+
+    /// Enables the detour at the provided target
+    ///
+    /// # Safety
+    ///
+    /// See [Detour::enable]
+    pub unsafe fn enable(&mut self, target: usize) -> Result<(), HookError> {
+        self.get_mut(target)?.enable()
+    }
+
+    /// Disables the detour at the provided target, the trampoline
+    /// remains valid until the detour is removed
+    ///
+    /// # Safety
+    ///
+    /// See [Detour::disable]
+    pub unsafe fn disable(&mut self, target: usize) -> Result<(), HookError> {
+        self.get_mut(target)?.disable()
+    }
+
+    /// Disables and removes the detour at the provided target freeing
+    /// its trampoline
+    ///
+    /// # Safety
+    ///
+    /// The original function pointer returned from [HookManager::install]
+    /// must not be used after the detour is removed
+    pub unsafe fn remove(&mut self, target: usize) -> Result<(), HookError> {
+        let index = self.find(target).ok_or(HookError::NotHooked(target))?;
+        self.detours[index].disable()?;
+        self.detours.remove(index);
+        Ok(())
+    }
+
+    /// Restores the original bytes of every detour, the trampolines
+    /// remain valid so in-flight calls can complete
+    ///
+    /// # Safety
+    ///
+    /// See [Detour::disable]
+    pub unsafe fn disable_all(&mut self) -> Result<(), HookError> {
+        self.detours
+            .iter_mut()
+            .try_for_each(|detour| detour.disable())
+    }
+
+    /// Removes every detour freeing their trampolines
+    ///
+    /// # Safety
+    ///
+    /// No thread may be executing within a trampoline and none of the
+    /// original function pointers may be used after this call
+    pub unsafe fn remove_all(&mut self) {
+        self.detours.clear();
+    }
+
+    /// Disables every detour and leaks their trampolines, used when
+    /// calls may still be executing within a trampoline
+    pub fn forget_all(&mut self) {
+        self.detours.drain(..).for_each(|mut detour| unsafe {
+            // Failing to restore here can't be recovered so the trampoline
+            // is kept alive either way
+            _ = detour.disable();
+            std::mem::forget(detour);
+        });
+    }
+}
+
+impl Default for HookManager {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Counter tracking the number of calls currently executing within
+/// hook functions, used to wait for hooks to drain before unloading
+pub struct ActiveCalls(AtomicUsize);
+
+impl ActiveCalls {
+    pub const fn new() -> Self {
+        Self(AtomicUsize::new(0))
+    }
+
+    /// Marks a call as active until the returned guard is dropped
+    pub fn enter(&self) -> ActiveCallGuard<'_> {
+        self.0.fetch_add(1, Ordering::AcqRel);
+        ActiveCallGuard(self)
+    }
+
+    /// Waits until no calls are active, returns false if the
+    /// timeout was reached before the calls drained
+    pub fn wait_idle(&self, timeout: Duration) -> bool {
+        let start = Instant::now();
+        while self.0.load(Ordering::Acquire) != 0 {
+            if start.elapsed() >= timeout {
+                return false;
+            }
+            std::thread::sleep(Duration::from_millis(1));
+        }
+        true
+    }
+}
+
+impl Default for ActiveCalls {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Guard for an active call, decrements the active count on drop
+pub struct ActiveCallGuard<'a>(&'a ActiveCalls);
+
+impl Drop for ActiveCallGuard<'_> {
+    fn drop(&mut self) {
+        self.0 .0.fetch_sub(1, Ordering::AcqRel);
+    }
+}
+
+/// Storage for the original function pointer of a detour which can be
+/// accessed without taking the hook manager lock
+pub struct Original<F> {
+    address: AtomicUsize,
+    _type: PhantomData<F>,
+}
+
+impl<F: Copy> Original<F> {
+    pub const fn new() -> Self {
+        Self {
+            address: AtomicUsize::new(0),
+            _type: PhantomData,
+        }
+    }
+
+    pub fn set(&self, value: F) {
+        let address: usize = unsafe { transmute_copy(&value) };
+        self.address.store(address, Ordering::Release);
+    }
+
+    pub fn clear(&self) {
+        self.address.store(0, Ordering::Release);
+    }
+
+    /// Gets the original function pointer if one is set
+    pub fn get(&self) -> Option<F> {
+        let address = self.address.load(Ordering::Acquire);
+        if address == 0 {
+            return None;
+        }
+
+        Some(unsafe { transmute_copy(&address) })
+    }
+}
+
+impl<F: Copy> Default for Original<F> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -1,27 +1,51 @@
 #![warn(unused_crate_dependencies)]
 
-use std::fs::File;
-use std::io::Write;
 use std::os::raw::c_void;
-
-use sdk::core::{FString, UFunction, UObject};
-use sdk::sfxgame::{FSFXOnlineMOTDInfo, USFXOnlineComponentUI};
-use serde::{Deserialize, Serialize};
-use windows_sys::Win32::System::Console::{AllocConsole, FreeConsole};
-use windows_sys::Win32::System::Memory::{
-    VirtualAlloc, VirtualProtect, MEM_COMMIT, MEM_RESERVE, PAGE_EXECUTE_READWRITE,
+use std::time::Duration;
+
+use addresses::Addresses;
+use config::config;
+use events::{events, EventAction, EventContext};
+use hook::detour::HookError;
+use hook::{ActiveCalls, HookManager, Original};
+use parking_lot::Mutex;
+use sdk::{
+    core::{set_game_names_address, set_game_objects_address, UFunction, UObject},
+    memory::set_gmalloc_address,
 };
+use windows_sys::Win32::System::Console::{AllocConsole, FreeConsole};
 use windows_sys::Win32::System::SystemServices::{DLL_PROCESS_ATTACH, DLL_PROCESS_DETACH};
 
-mod sdk;
+pub mod addresses;
+pub mod config;
+pub mod events;
+pub mod guard;
+pub mod hook;
+pub mod logging;
+pub mod messages;
+pub mod scanner;
+pub mod sdk;
+pub mod trace;
+pub mod writer;
 
 type ProcessEvent =
     unsafe extern "thiscall" fn(*mut UObject, *mut UFunction, *mut c_void, *mut c_void);
 
-const JMP_SIZE: usize = 5; // Size of a near jump instruction in x86
+/// Manager holding all the installed hooks
+static HOOKS: Mutex<HookManager> = Mutex::new(HookManager::new());
+
+/// Original ProcessEvent function
+static ORIGINAL_PROCESS_EVENT: Original<ProcessEvent> = Original::new();
 
-static mut ORIGINAL_BYTES: [u8; JMP_SIZE] = [0; JMP_SIZE];
-static mut ORIGINAL_FUNCTION: Option<ProcessEvent> = None;
+/// Hooked ProcessEvent function, calls the original function once the hook
+/// is removed and the original bytes are restored
+static HOOKED_PROCESS_EVENT: Original<ProcessEvent> = Original::new();
+
+/// Calls currently executing within the hook functions
+static ACTIVE_CALLS: ActiveCalls = ActiveCalls::new();
+
+/// Maximum time to wait for in-flight hook calls to complete when unloading
+const UNLOAD_DRAIN_TIMEOUT: Duration = Duration::from_secs(5);
 
 /// # Safety
 pub unsafe fn process_event(
@@ -30,109 +54,134 @@ pub unsafe fn process_event(
     params: *mut c_void,
     result: *mut c_void,
 ) {
+    let original = ORIGINAL_PROCESS_EVENT
+        .get()
+        .expect("ProcessEvent hook not installed");
+
     // Call the original function
-    (ORIGINAL_FUNCTION.unwrap())(this, func, params, result);
+    original(this, func, params, result);
 }
 
-unsafe fn hook_function_address(target: *mut u8, hook: *const u8) {
-    // Save original bytes
-    std::ptr::copy_nonoverlapping(target, ORIGINAL_BYTES.as_mut_ptr(), JMP_SIZE);
-
-    // Construct the jump instruction to the hook function
-    let relative_offset = hook as isize - target as isize - JMP_SIZE as isize;
-    let jmp_instruction = [
-        0xE9,
-        (relative_offset & 0xFF) as u8,
-        ((relative_offset >> 8) & 0xFF) as u8,
-        ((relative_offset >> 16) & 0xFF) as u8,
-        ((relative_offset >> 24) & 0xFF) as u8,
-    ];
-
-    // Change memory permissions to writable
-    let mut old_protect: u32 = 0;
-    VirtualProtect(
-        target as *mut _,
-        JMP_SIZE,
-        PAGE_EXECUTE_READWRITE,
-        &mut old_protect,
-    );
-
-    // Write the jump instruction to the target function
-    std::ptr::copy_nonoverlapping(jmp_instruction.as_ptr(), target, JMP_SIZE);
-
-    // Restore memory permissions
-    VirtualProtect(target as *mut _, JMP_SIZE, old_protect, &mut old_protect);
+/// Installs and enables the ProcessEvent hook at the provided address
+unsafe fn hook_process_event(address: usize) -> Result<(), HookError> {
+    let mut hooks = HOOKS.lock();
+    let original = hooks.install::<ProcessEvent>(address, fake_process_event)?;
+    ORIGINAL_PROCESS_EVENT.set(original);
+    HOOKED_PROCESS_EVENT.set(std::mem::transmute::<usize, ProcessEvent>(address));
+    hooks.enable(address)
+}
 
-    // Calculate the address of the original function after the JMP instruction
-    let trampoline_size = JMP_SIZE;
-    let trampoline = VirtualAlloc(
-        std::ptr::null_mut(),
-        trampoline_size,
-        MEM_COMMIT | MEM_RESERVE,
-        PAGE_EXECUTE_READWRITE,
-    );
+/// Removes all the hooks restoring the original function bytes, waits for
+/// any calls still executing within the hooks to complete, and closes the
+/// event log. Must not be called from within a hook.
+#[no_mangle]
+pub extern "C" fn unload() {
+    shutdown(true);
+}
 
-    if trampoline.is_null() {
-        panic!("Failed to allocate memory for trampoline");
+/// Removes the hooks and closes the event log, background threads are
+/// only joined when `join` is true as they can't be joined from DllMain
+fn shutdown(join: bool) {
+    let mut hooks = HOOKS.lock();
+
+    // Restore original bytes so no new calls enter the hooks
+    if let Err(err) = unsafe { hooks.disable_all() } {
+        error!("Failed to restore hooked functions: {}", err);
+        hooks.forget_all();
+    } else if ACTIVE_CALLS.wait_idle(UNLOAD_DRAIN_TIMEOUT) {
+        ORIGINAL_PROCESS_EVENT.clear();
+        unsafe { hooks.remove_all() };
+    } else {
+        // Calls may still be running within a trampoline so it can't be freed
+        warn!("Timed out waiting for hooked calls to complete");
+        hooks.forget_all();
     }
 
-    std::ptr::copy_nonoverlapping(ORIGINAL_BYTES.as_ptr(), trampoline as *mut u8, JMP_SIZE);
-
-    let jump_back_offset =
-        (target.add(JMP_SIZE) as isize - trampoline as isize - JMP_SIZE as isize) as u32;
-    let jump_back = [
-        0xE9,
-        (jump_back_offset & 0xFF) as u8,
-        ((jump_back_offset >> 8) & 0xFF) as u8,
-        ((jump_back_offset >> 16) & 0xFF) as u8,
-        ((jump_back_offset >> 24) & 0xFF) as u8,
-    ];
-
-    std::ptr::copy_nonoverlapping(
-        jump_back.as_ptr(),
-        trampoline.add(JMP_SIZE) as *mut u8,
-        jump_back.len(),
-    );
+    // Queued messages can't be displayed once unhooked
+    let flushed = messages::flush_queue();
+    if flushed > 0 {
+        debug!("Discarded {} queued system messages", flushed);
+    }
 
-    // Save the original function pointer, adjusted to skip the JMP instruction
-    ORIGINAL_FUNCTION = Some(std::mem::transmute::<*mut c_void, ProcessEvent>(trampoline));
+    // Close the event log
+    trace::close(join);
 }
 
 /// Windows DLL entrypoint for the plugin
 #[no_mangle]
-extern "stdcall" fn DllMain(_hmodule: isize, reason: u32, _: *mut ()) -> bool {
+extern "stdcall" fn DllMain(_hmodule: isize, reason: u32, reserved: *mut ()) -> bool {
     if let DLL_PROCESS_ATTACH = reason {
+        guard::catch("DllMain", attach);
+    } else if let DLL_PROCESS_DETACH = reason {
+        guard::catch("DllMain", || detach(reserved.is_null()));
+    }
+
+    true
+}
+
+/// Loads the config and installs the hooks when the plugin is attached
+fn attach() {
+    let errors = config::init();
+    let config = config();
+
+    logging::set_level(config.log_level);
+    guard::init(config.panic_limit);
+
+    if config.console {
         unsafe {
             AllocConsole();
         }
+    }
 
-        unsafe { MESSAGES = Some(File::create("event-dump.txt").unwrap()) }
+    // Errors are logged after the console is available
+    for err in errors {
+        error!("Invalid config: {}", err);
+    }
 
-        unsafe {
-            hook_function_address(
-                0x00453120 as *const u8 as *mut u8,
-                fake_process_event as *const u8,
-            );
-        }
-    } else if let DLL_PROCESS_DETACH = reason {
-        unsafe {
-            FreeConsole();
-        }
+    trace::init(&config.events);
+
+    messages::register(&mut events().lock());
+
+    let addresses = unsafe { Addresses::resolve(&config.offsets) };
+    match addresses.game_objects {
+        Some(address) => set_game_objects_address(address),
+        None => warn!("GObjects not resolved, objects can't be looked up"),
+    }
+    if let Some(address) = addresses.game_names {
+        set_game_names_address(address);
+    }
+    match addresses.gmalloc {
+        Some(address) => set_gmalloc_address(address),
+        None => error!(
+            "GMalloc not configured, engine arrays and strings can't be allocated \
+             and plugin strings use the Rust allocator"
+        ),
     }
 
-    true
+    let Some(process_event) = addresses.process_event else {
+        error!("ProcessEvent not resolved, configure its address to enable the plugin");
+        return;
+    };
+
+    if let Err(err) = unsafe { hook_process_event(process_event) } {
+        error!("Failed to hook ProcessEvent: {}", err);
+    }
 }
 
-static mut MESSAGES: Option<File> = None;
+/// Removes the hooks when the plugin is detached, `unloading` is false
+/// when the process is terminating and the other threads are already stopped
+fn detach(unloading: bool) {
+    if unloading {
+        shutdown(false);
+    } else {
+        trace::close(false);
+    }
 
-#[derive(Deserialize, Serialize)]
-pub struct SystemMessage {
-    title: String,
-    message: String,
-    image: String,
-    ty: u8,
-    tracking_id: i32,
-    priority: i32,
+    if config().console {
+        unsafe {
+            FreeConsole();
+        }
+    }
 }
 
 #[allow(clippy::missing_safety_doc)]
@@ -143,85 +192,65 @@ pub unsafe extern "thiscall" fn fake_process_event(
     params: *mut c_void,
     result: *mut c_void,
 ) {
-    let mut name = func.read().as_object_ref().get_full_name();
-    name.push('\n');
-
-    // Log the processed event full function name by writing it to a file
-    MESSAGES
-        .as_mut()
-        .unwrap()
-        .write_all(name.as_bytes())
-        .unwrap();
-
-    // Hook existing display notification event code
-    if name.contains("Function SFXGame.SFXOnlineComponentUI.OnDisplayNotification") {
-        #[derive(Debug, Clone, Copy)]
-        #[repr(C)]
-        #[allow(non_camel_case_types)]
-        struct Params {
-            info: FSFXOnlineMOTDInfo,
+    // Entered before anything else so the call is counted while unloading
+    let _active = ACTIVE_CALLS.enter();
+
+    let original = match ORIGINAL_PROCESS_EVENT.get() {
+        Some(value) => value,
+        None => {
+            // Entered the hook just before it was removed, the trampoline is
+            // gone but the original bytes have been restored at the function
+            if let Some(hooked) = HOOKED_PROCESS_EVENT.get() {
+                hooked(object, func, params, result);
+            }
+            return;
         }
+    };
 
-        let original_params = &params
-            .cast::<Params>()
-            .as_mut()
-            .expect("OnDisplayNotification params were null")
-            .info;
-
-        // Log message call
-        {
-            let messages = MESSAGES.as_mut().unwrap();
+    // Custom handling is skipped entirely in safe mode, a panic
+    // falls through to the original function
+    if guard::is_safe_mode() {
+        original(object, func, params, result);
+        return;
+    }
 
-            writeln!(messages, "MESSAGE: {:?}", original_params).unwrap();
+    let handlers = guard::catch("ProcessEvent", || {
+        trace::record(object, func);
+        events().lock().get(func)
+    })
+    .flatten();
+
+    let mut context = EventContext {
+        object,
+        function: func,
+        params,
+        result,
+    };
+
+    if let Some(handlers) = &handlers {
+        let action = guard::catch("ProcessEvent", || handlers.run_pre(&mut context));
+        if action == Some(EventAction::Suppress) {
+            return;
         }
+    }
 
-        let original_message = &original_params.message.to_string();
-
-        // Handle system messages
-        if let Some(message) = original_message.strip_prefix("[SYSTEM_TERMINAL]") {
-            let value = serde_json::from_str::<SystemMessage>(
-                // Stip all non JSON data from the end of the payload
-                message.trim_end_matches(|value| value != '}'),
-            );
-
-            if let Ok(message) = value {
-                // Get mutable reference to type
-                let this = object
-                    .cast::<USFXOnlineComponentUI>()
-                    .as_mut()
-                    .expect("USFXOnlineComponentUI class was null");
-
-                // Send custom message instead
-                this.event_on_display_notification(FSFXOnlineMOTDInfo {
-                    title: FString::from_string(message.title),
-                    message: FString::from_string(message.message),
-                    image: FString::from_string(message.image),
-                    tracking_id: message.tracking_id,
-                    priority: message.priority,
-                    bw_ent_id: 0,
-                    offer_id: 0,
-                    ty: message.ty,
-                });
-
-                return;
-            }
-        }
+    original(
+        context.object,
+        context.function,
+        context.params,
+        context.result,
+    );
+
+    if let Some(handlers) = &handlers {
+        guard::catch("ProcessEvent", || handlers.run_post(&context));
     }
 
-    process_event(object, func, params, result);
+    // Expired confirmation codes are cleared and queued messages are
+    // displayed outside of the handlers
+    if messages::is_code_expired() {
+        guard::catch("ProcessEvent", || messages::clear_expired_code());
+    }
+    if messages::is_queue_ready() {
+        guard::catch("ProcessEvent", || messages::display_queued());
+    }
 }
-
-// Enum SFXOnlineFoundation.SFXOnlineDefine.SFXOnlineConnection_MessageType
-/*enum SFXOnlineConnection_MessageType
-{
-    SFXONLINE_MT_MESSAGEOFTHEDAY                       = 0,
-    SFXONLINE_MT_DOWNLOAD_PROMPT                       = 1,
-    SFXONLINE_MT_GAW_SUMMARY                           = 2,
-    SFXONLINE_MT_GAW_STATUS_UPDATE                     = 3,
-    SFXONLINE_MT_FRIEND_ACHIVEMENT                     = 4,
-    SFXONLINE_MT_FRIEND_LEADERBOARD_RANK_CHANGE        = 5,
-    SFXONLINE_MT_MESSAGEOFTHEDAY_TICKERONLY            = 6,
-    SFXONLINE_MT_DISCONNECTED_TICKERONLY               = 7,
-    SFXONLINE_MT_MP_PROMO                              = 8,
-    SFXONLINE_MT_MAX                                   = 9
-};*/
@@ -0,0 +1,190 @@
+//! Acknowledgements sent back to the server once a message has been
+//! displayed, an [Ack] envelope is posted to the configured URL
+
+use super::protocol::{Ack, Envelope, Message};
+use crate::{debug, warn};
+use std::{
+    io::{BufRead, BufReader, Error, ErrorKind, Read, Write},
+    net::{TcpStream, ToSocketAddrs},
+    time::Duration,
+};
+
+/// Timeout for connecting to the server and for each read and write
+const TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Maximum length of the response status line
+const MAX_STATUS_LENGTH: u64 = 1024;
+
+/// Plain HTTP URL in the form `http://host[:port][/path]`, IPv6 hosts
+/// are enclosed in brackets (e.g. `http://[::1]:80/ack`)
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HttpUrl<'a> {
+    /// Host name or address, without the brackets for IPv6 addresses
+    pub host: &'a str,
+    pub port: u16,
+    pub path: &'a str,
+}
+
+impl<'a> HttpUrl<'a> {
+    /// Parses the URL, returns [None] for URLs that aren't plain HTTP
+    pub fn parse(value: &'a str) -> Option<Self> {
+        let rest = value.strip_prefix("http://")?;
+        let (authority, path) = match rest.find('/') {
+            Some(index) => rest.split_at(index),
+            None => (rest, "/"),
+        };
+
+        // Port follows the closing bracket of IPv6 addresses
+        let (host, port) = match authority.strip_prefix('[') {
+            Some(rest) => rest.split_once(']')?,
+            None => match authority.find(':') {
+                Some(index) => authority.split_at(index),
+                None => (authority, ""),
+            },
+        };
+
+        let port = match port {
+            "" => 80,
+            port => port.strip_prefix(':')?.parse().ok()?,
+        };
+
+        if host.is_empty() {
+            return None;
+        }
+
+        Some(Self { host, port, path })
+    }
+
+    /// Host for the Host header, IPv6 addresses are enclosed in brackets
+    fn host_header(&self) -> String {
+        if self.host.contains(':') {
+            format!("[{}]:{}", self.host, self.port)
+        } else {
+            format!("{}:{}", self.host, self.port)
+        }
+    }
+}
+
+/// Acknowledges the message with the provided ID, the request is sent
+/// from a background thread so the game isn't blocked
+pub fn send(url: &str, id: &str) {
+    let body = Envelope::new(Message::Ack(Ack { id: id.to_string() })).encode();
+    let url = url.to_string();
+    let id = id.to_string();
+
+    let result = std::thread::Builder::new()
+        .name("deep-link-ack".to_string())
+        .spawn(move || match post(&url, &body) {
+            Ok(200..=299) => debug!("Acknowledged message {}", id),
+            Ok(status) => warn!("Acknowledgement for {} failed with status {}", id, status),
+            Err(err) => warn!("Failed to acknowledge {}: {}", id, err),
+        });
+
+    if let Err(err) = result {
+        warn!("Failed to start acknowledgement thread: {}", err);
+    }
+}
+
+/// Posts the JSON body to the URL returning the response status code
+fn post(url: &str, body: &str) -> std::io::Result<u16> {
+    let url =
+        HttpUrl::parse(url).ok_or_else(|| Error::new(ErrorKind::InvalidInput, "invalid URL"))?;
+
+    let address = (url.host, url.port)
+        .to_socket_addrs()?
+        .next()
+        .ok_or_else(|| Error::new(ErrorKind::NotFound, "host not found"))?;
+
+    let mut stream = TcpStream::connect_timeout(&address, TIMEOUT)?;
+    stream.set_read_timeout(Some(TIMEOUT))?;
+    stream.set_write_timeout(Some(TIMEOUT))?;
+
+    write!(
+        stream,
+        "POST {} HTTP/1.1\r\n\
+        Host: {}\r\n\
+        Content-Type: application/json\r\n\
+        Content-Length: {}\r\n\
+        Connection: close\r\n\r\n{}",
+        url.path,
+        url.host_header(),
+        body.len(),
+        body
+    )?;
+
+    // Only the status line is needed (e.g. "HTTP/1.1 200 OK")
+    let mut status = String::new();
+    BufReader::new(stream.take(MAX_STATUS_LENGTH)).read_line(&mut status)?;
+
+    status
+        .split_whitespace()
+        .nth(1)
+        .and_then(|value| value.parse().ok())
+        .ok_or_else(|| Error::new(ErrorKind::InvalidData, "invalid response"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn url<'a>(host: &'a str, port: u16, path: &'a str) -> Option<HttpUrl<'a>> {
+        Some(HttpUrl { host, port, path })
+    }
+
+    #[test]
+    fn parse() {
+        assert_eq!(
+            HttpUrl::parse("http://localhost"),
+            url("localhost", 80, "/")
+        );
+        assert_eq!(
+            HttpUrl::parse("http://localhost:8080"),
+            url("localhost", 8080, "/")
+        );
+        assert_eq!(
+            HttpUrl::parse("http://127.0.0.1/api/ack"),
+            url("127.0.0.1", 80, "/api/ack")
+        );
+        assert_eq!(
+            HttpUrl::parse("http://example.com:42/ack"),
+            url("example.com", 42, "/ack")
+        );
+    }
+
+    #[test]
+    fn parse_ipv6() {
+        assert_eq!(HttpUrl::parse("http://[::1]:80"), url("::1", 80, "/"));
+        assert_eq!(HttpUrl::parse("http://[::1]"), url("::1", 80, "/"));
+        assert_eq!(
+            HttpUrl::parse("http://[fe80::1:2]:8080/ack"),
+            url("fe80::1:2", 8080, "/ack")
+        );
+
+        // Addresses must be enclosed in brackets
+        assert_eq!(HttpUrl::parse("http://::1:80"), None);
+        assert_eq!(HttpUrl::parse("http://[::1"), None);
+        assert_eq!(HttpUrl::parse("http://[::1]80"), None);
+        assert_eq!(HttpUrl::parse("http://[]:80"), None);
+    }
+
+    #[test]
+    fn parse_invalid() {
+        assert_eq!(HttpUrl::parse("https://localhost"), None);
+        assert_eq!(HttpUrl::parse("localhost:80"), None);
+        assert_eq!(HttpUrl::parse("http://"), None);
+        assert_eq!(HttpUrl::parse("http://:80"), None);
+        assert_eq!(HttpUrl::parse("http://localhost:"), None);
+        assert_eq!(HttpUrl::parse("http://localhost:port"), None);
+        assert_eq!(HttpUrl::parse("http://localhost:65536"), None);
+        assert_eq!(HttpUrl::parse("http://localhost:80:80"), None);
+    }
+
+    #[test]
+    fn host_header() {
+        let value = HttpUrl::parse("http://localhost/ack").unwrap();
+        assert_eq!(value.host_header(), "localhost:80");
+
+        let value = HttpUrl::parse("http://[::1]:8080/ack").unwrap();
+        assert_eq!(value.host_header(), "[::1]:8080");
+    }
+}
@@ -0,0 +1,297 @@
+//! Framing of the system messages within the notification text, the text
+//! after the prefix contains one or more envelopes each sent as either:
+//!
+//! - The envelope JSON object
+//! - A JSON string containing the envelope JSON
+//! - `base64:` followed by the standard base64 of the envelope JSON, the
+//!   base64 ends at the first character outside of the base64 alphabet
+//!
+//! Escaped and base64 envelopes allow arbitrary content to survive the
+//! MOTD transport. Any text after the last envelope is left over and ignored
+
+use super::protocol::{Envelope, ProtocolError};
+use serde_json::{Deserializer, Value};
+
+/// Prefix for base64 encoded envelopes
+pub const BASE64_PREFIX: &str = "base64:";
+
+/// Decodes the envelopes from the text, returning the decoded envelopes
+/// and the text left over after the last envelope. Decoding stops at
+/// the first malformed envelope as the end of its value isn't known
+pub fn decode_payload(value: &str) -> (Vec<Result<Envelope, ProtocolError>>, &str) {
+    let mut envelopes = Vec::new();
+    let mut rest = value.trim_start();
+
+    while !rest.is_empty() {
+        if let Some(encoded) = rest.strip_prefix(BASE64_PREFIX) {
+            let length = encoded
+                .find(|value: char| !is_base64(value))
+                .unwrap_or(encoded.len());
+            let (encoded, next) = encoded.split_at(length);
+
+            let json = decode_base64(encoded)
+                .and_then(|value| String::from_utf8(value).ok())
+                .ok_or(ProtocolError::InvalidEncoding);
+            envelopes.push(json.and_then(|value| Envelope::decode(&value)));
+
+            rest = next.trim_start();
+            continue;
+        }
+
+        // Remaining text isn't an envelope
+        if !rest.starts_with(['{', '"']) {
+            break;
+        }
+
+        // Reads exactly one value leaving the rest of the text
+        let mut stream = Deserializer::from_str(rest).into_iter::<Value>();
+        let envelope = match stream.next() {
+            Some(Ok(Value::String(value))) => Envelope::decode(&value),
+            Some(Ok(value)) => Envelope::from_value(value),
+            Some(Err(err)) => {
+                envelopes.push(Err(ProtocolError::Malformed(err)));
+                break;
+            }
+            None => break,
+        };
+        envelopes.push(envelope);
+
+        rest = rest[stream.byte_offset()..].trim_start();
+    }
+
+    (envelopes, rest)
+}
+
+/// Encodes the envelope as base64 including the prefix
+pub fn encode_base64(envelope: &Envelope) -> String {
+    let mut out = BASE64_PREFIX.to_string();
+    out.push_str(&encode_base64_bytes(envelope.encode().as_bytes()));
+    out
+}
+
+/// Encodes the bytes as standard base64 with padding
+fn encode_base64_bytes(value: &[u8]) -> String {
+    const ALPHABET: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+    let mut out = String::with_capacity((value.len() + 2) / 3 * 4);
+
+    for chunk in value.chunks(3) {
+        let mut buffer = [0u8; 3];
+        buffer[..chunk.len()].copy_from_slice(chunk);
+        let value = u32::from_be_bytes([0, buffer[0], buffer[1], buffer[2]]);
+
+        for index in 0..4 {
+            if index <= chunk.len() {
+                let digit = (value >> (18 - index * 6)) & 0x3F;
+                out.push(ALPHABET[digit as usize] as char);
+            } else {
+                out.push('=');
+            }
+        }
+    }
+
+    out
+}
+
+fn is_base64(value: char) -> bool {
+    value.is_ascii_alphanumeric() || matches!(value, '+' | '/' | '=')
+}
+
+/// Decodes standard base64 with optional padding
+fn decode_base64(value: &str) -> Option<Vec<u8>> {
+    let value = value.trim_end_matches('=');
+
+    // A single trailing digit can't encode a full byte
+    if value.len() % 4 == 1 {
+        return None;
+    }
+
+    let mut out = Vec::with_capacity(value.len() * 3 / 4);
+    let mut buffer: u32 = 0;
+    let mut bits = 0;
+
+    for byte in value.bytes() {
+        let digit = match byte {
+            b'A'..=b'Z' => byte - b'A',
+            b'a'..=b'z' => byte - b'a' + 26,
+            b'0'..=b'9' => byte - b'0' + 52,
+            b'+' => 62,
+            b'/' => 63,
+            _ => return None,
+        };
+
+        buffer = (buffer << 6) | digit as u32;
+        bits += 6;
+
+        if bits >= 8 {
+            bits -= 8;
+            out.push((buffer >> bits) as u8);
+            buffer &= (1 << bits) - 1;
+        }
+    }
+
+    Some(out)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::messages::protocol::{Ack, Message};
+
+    fn ack(id: &str) -> Envelope {
+        Envelope::new(Message::Ack(Ack { id: id.to_string() }))
+    }
+
+    #[test]
+    fn single_envelope() {
+        let (envelopes, rest) =
+            decode_payload(r#"  {"v": 1, "kind": "ack", "payload": {"id": "1"}}  "#);
+        assert_eq!(envelopes.len(), 1);
+        assert_eq!(envelopes[0].as_ref().unwrap(), &ack("1"));
+        assert_eq!(rest, "");
+    }
+
+    #[test]
+    fn brace_in_string() {
+        let (envelopes, rest) =
+            decode_payload(r#"{"v": 1, "kind": "ack", "payload": {"id": "}{\"}"}} trailing }"#);
+        assert_eq!(envelopes.len(), 1);
+        assert_eq!(envelopes[0].as_ref().unwrap(), &ack("}{\"}"));
+        assert_eq!(rest, "trailing }");
+    }
+
+    #[test]
+    fn multiple_envelopes() {
+        let payload = format!(
+            "{}{} {}\n{}",
+            ack("1").encode(),
+            ack("2").encode(),
+            serde_json::to_string(&ack("3").encode()).unwrap(),
+            encode_base64(&ack("4")),
+        );
+
+        let (envelopes, rest) = decode_payload(&payload);
+        let envelopes: Vec<Envelope> = envelopes.into_iter().map(Result::unwrap).collect();
+        assert_eq!(envelopes, [ack("1"), ack("2"), ack("3"), ack("4")]);
+        assert_eq!(rest, "");
+    }
+
+    #[test]
+    fn string_envelope() {
+        let payload = serde_json::to_string(&ack("}\"").encode()).unwrap();
+        let payload = format!("{} rest", payload);
+
+        let (envelopes, rest) = decode_payload(&payload);
+        assert_eq!(envelopes.len(), 1);
+        assert_eq!(envelopes[0].as_ref().unwrap(), &ack("}\""));
+        assert_eq!(rest, "rest");
+
+        // String that doesn't contain an envelope
+        let (envelopes, rest) = decode_payload(r#""text" rest"#);
+        assert!(matches!(envelopes[..], [Err(ProtocolError::Malformed(_))]));
+        assert_eq!(rest, "rest");
+    }
+
+    #[test]
+    fn errors_continue() {
+        let payload = format!(
+            r#"{{"v": 1, "kind": "future"}} {{"v": 2, "kind": "ack"}} {}"#,
+            ack("1").encode()
+        );
+
+        let (envelopes, rest) = decode_payload(&payload);
+        assert!(matches!(
+            envelopes[..],
+            [
+                Err(ProtocolError::UnknownKind(_)),
+                Err(ProtocolError::UnsupportedVersion(2)),
+                Ok(_)
+            ]
+        ));
+        assert_eq!(rest, "");
+    }
+
+    #[test]
+    fn malformed_stops() {
+        let payload = format!(
+            r#"{} {{"v": 1, "kind" }} {}"#,
+            ack("1").encode(),
+            ack("2").encode()
+        );
+
+        let (envelopes, _) = decode_payload(&payload);
+        assert_eq!(envelopes.len(), 2);
+        assert!(envelopes[0].is_ok());
+        assert!(matches!(envelopes[1], Err(ProtocolError::Malformed(_))));
+    }
+
+    #[test]
+    fn leftover_text() {
+        let (envelopes, rest) = decode_payload("Plain notification text");
+        assert!(envelopes.is_empty());
+        assert_eq!(rest, "Plain notification text");
+
+        let (envelopes, rest) = decode_payload("");
+        assert!(envelopes.is_empty());
+        assert_eq!(rest, "");
+    }
+
+    #[test]
+    fn base64_bytes() {
+        let values: [(&[u8], &str); 5] = [
+            (b"", ""),
+            (b"f", "Zg=="),
+            (b"fo", "Zm8="),
+            (b"foo", "Zm9v"),
+            (b"foob", "Zm9vYg=="),
+        ];
+
+        for (value, encoded) in values {
+            assert_eq!(encode_base64_bytes(value), encoded);
+            assert_eq!(decode_base64(encoded).unwrap(), value);
+            assert_eq!(decode_base64(encoded.trim_end_matches('=')).unwrap(), value);
+        }
+
+        assert_eq!(encode_base64_bytes(&[0xFB, 0xFF]), "+/8=");
+        assert_eq!(decode_base64("+/8=").unwrap(), [0xFB, 0xFF]);
+    }
+
+    #[test]
+    fn base64_round_trip() {
+        // Each length of ID covers a different amount of padding
+        for length in 0..=4 {
+            let envelope = ack(&"x".repeat(length));
+            let encoded = encode_base64(&envelope);
+
+            let payload = format!("{} rest", encoded);
+            let (envelopes, rest) = decode_payload(&payload);
+            assert_eq!(envelopes.len(), 1);
+            assert_eq!(envelopes[0].as_ref().unwrap(), &envelope);
+            assert_eq!(rest, "rest");
+        }
+    }
+
+    #[test]
+    fn base64_invalid() {
+        // Single trailing digit and invalid UTF-8
+        for payload in ["base64:Q", "base64:/w=="] {
+            let (envelopes, rest) = decode_payload(payload);
+            assert!(
+                matches!(envelopes[..], [Err(ProtocolError::InvalidEncoding)]),
+                "{}",
+                payload
+            );
+            assert_eq!(rest, "");
+        }
+
+        // Valid encoding that isn't an envelope
+        let (envelopes, _) = decode_payload("base64:e30=");
+        assert!(matches!(envelopes[..], [Err(ProtocolError::Malformed(_))]));
+
+        // Base64 ends at the first character outside the alphabet
+        let payload = format!("{}!{}", encode_base64(&ack("1")), ack("2").encode());
+        let (envelopes, rest) = decode_payload(&payload);
+        assert_eq!(envelopes.len(), 1);
+        assert_eq!(rest, format!("!{}", ack("2").encode()));
+    }
+}
@@ -0,0 +1,507 @@
+//! Handling for system messages sent by the server through the
+//! message of the day notifications
+
+use crate::{
+    config::config,
+    debug, error,
+    events::{EventAction, EventContext, EventRegistry},
+    sdk::{
+        core::{first_instance_of, OwnedFString, SdkError},
+        params::UFunctionParams,
+        sfxgame::{
+            ClearNotificationsParams, FSFXOnlineMOTDInfo, OnDisplayNotificationParams,
+            USFXOnlineComponentUI,
+        },
+    },
+    warn,
+};
+use parking_lot::Mutex;
+use protocol::{ConfirmationCode, Message, MessageType, Notification};
+use queue::{NotificationQueue, PushResult};
+use std::{
+    collections::VecDeque,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        OnceLock,
+    },
+    time::{Duration, Instant},
+};
+
+pub mod ack;
+pub mod framing;
+pub mod protocol;
+pub mod queue;
+
+/// Title of the confirmation code notifications
+const CONFIRMATION_CODE_TITLE: &str = "Origin Confirmation Code";
+
+/// Maximum number of displayed notifications remembered to restore the
+/// message terminal after an expired confirmation code is cleared
+const DISPLAYED_LIMIT: usize = 32;
+
+/// Priority of the notifications displayed again after an expired
+/// confirmation code is cleared, after codes and before other messages
+const RESTORE_PRIORITY: i32 = i32::MAX - 1;
+
+/// Delay before looking for the message terminal again when displaying
+/// a queued message and the terminal doesn't exist yet
+const TERMINAL_RETRY: Duration = Duration::from_secs(1);
+
+/// Time the displayed confirmation code expires as milliseconds since
+/// [CLOCK_START], zero when no code is displayed
+static CODE_EXPIRY: AtomicU64 = AtomicU64::new(0);
+
+/// Time the next queued message can be displayed as milliseconds since
+/// [CLOCK_START], zero when the queue is empty
+static QUEUE_READY: AtomicU64 = AtomicU64::new(0);
+
+/// Start of the clock used for [CODE_EXPIRY] and [QUEUE_READY]
+static CLOCK_START: OnceLock<Instant> = OnceLock::new();
+
+/// Notifications displayed in the message terminal since it was last
+/// cleared, oldest first
+static DISPLAYED: Mutex<VecDeque<Notification>> = Mutex::new(VecDeque::new());
+
+/// Queue of messages waiting to be displayed
+static QUEUE: OnceLock<Mutex<NotificationQueue<PendingMessage>>> = OnceLock::new();
+
+/// Message waiting in the queue to be displayed
+#[derive(Debug)]
+pub enum PendingMessage {
+    Notification(Notification),
+    ConfirmationCode(ConfirmationCode),
+}
+
+/// Gets the queue of messages waiting to be displayed
+pub fn queue() -> &'static Mutex<NotificationQueue<PendingMessage>> {
+    QUEUE.get_or_init(|| {
+        let config = &config().messages;
+        Mutex::new(NotificationQueue::new(
+            config.queue_capacity,
+            Duration::from_millis(config.display_interval_ms),
+        ))
+    })
+}
+
+/// Registers the message event handlers
+pub fn register(events: &mut EventRegistry) {
+    events.on_pre(on_display_notification);
+    events.register_pre(ClearNotificationsParams::FUNCTION, on_clear_notifications);
+}
+
+/// Hook existing display notification event code
+unsafe fn on_display_notification(
+    params: &mut OnDisplayNotificationParams,
+    context: &mut EventContext,
+) -> EventAction {
+    let config = config();
+
+    let original_message = &params.info.message.to_string();
+
+    // Handle system messages
+    let message = match original_message
+        .strip_prefix(config.messages.prefix.as_str())
+        .filter(|_| config.messages.enabled)
+    {
+        Some(value) => value,
+        None => {
+            debug!("MESSAGE: {:?}", params.info);
+            record_displayed(notification_from_info(&params.info));
+            return EventAction::Continue;
+        }
+    };
+
+    let (envelopes, leftover) = framing::decode_payload(message);
+    if !leftover.is_empty() {
+        debug!("Ignoring {} bytes after system messages", leftover.len());
+    }
+
+    if envelopes.is_empty() {
+        warn!("System message didn't contain any envelopes");
+    }
+
+    // Invalid messages are dropped rather than showing the raw JSON
+    for envelope in envelopes {
+        let message = match envelope {
+            Ok(value) => value.message,
+            Err(err) => {
+                // Serde errors can include the payload so only the category is logged
+                warn!("Ignoring system message: {}", err.category());
+                continue;
+            }
+        };
+
+        let (priority, tracking_id, message) = match message {
+            Message::Notification(notification) => (
+                notification.priority,
+                notification.tracking_id,
+                PendingMessage::Notification(notification),
+            ),
+            // Codes are time limited so they're displayed first
+            Message::ConfirmationCode(code) => {
+                (i32::MAX, 0, PendingMessage::ConfirmationCode(code))
+            }
+            Message::Ack(_) => {
+                warn!("Ignoring acknowledgement sent by the server");
+                continue;
+            }
+        };
+
+        match queue().lock().push(priority, tracking_id, message) {
+            PushResult::Queued | PushResult::Replaced => {}
+            PushResult::Duplicate => debug!("Skipping repeated message {}", tracking_id),
+            PushResult::Full => warn!("Message queue is full, dropping message"),
+        }
+    }
+
+    // Get mutable reference to type
+    let this = context
+        .object::<USFXOnlineComponentUI>()
+        .expect("USFXOnlineComponentUI class was null");
+
+    display_next(this);
+
+    EventAction::Suppress
+}
+
+/// Forgets the displayed notifications when the game clears the message terminal
+unsafe fn on_clear_notifications(_context: &mut EventContext) -> EventAction {
+    DISPLAYED.lock().clear();
+    EventAction::Continue
+}
+
+/// Creates a notification from the game notification info
+fn notification_from_info(info: &FSFXOnlineMOTDInfo) -> Notification {
+    let info = *info;
+    Notification {
+        title: info.title.to_string(),
+        message: info.message.to_string(),
+        image: info.image.to_string(),
+        ty: MessageType::try_from(info.ty).unwrap_or_default(),
+        tracking_id: info.tracking_id,
+        priority: info.priority,
+    }
+}
+
+/// Remembers a notification displayed in the message terminal, either by
+/// the game or by [display_notification]
+fn record_displayed(notification: Notification) {
+    push_displayed(&mut DISPLAYED.lock(), notification);
+}
+
+/// Adds the notification to the displayed notifications, only the most
+/// recent [DISPLAYED_LIMIT] are kept
+fn push_displayed(displayed: &mut VecDeque<Notification>, notification: Notification) {
+    if displayed.len() >= DISPLAYED_LIMIT {
+        displayed.pop_front();
+    }
+    displayed.push_back(notification);
+}
+
+/// Selects the displayed notifications to display again once the message
+/// terminal is cleared, oldest first. Confirmation codes are never restored
+fn select_restore(displayed: VecDeque<Notification>) -> Vec<Notification> {
+    displayed
+        .into_iter()
+        .filter(|notification| notification.title != CONFIRMATION_CODE_TITLE)
+        .collect()
+}
+
+/// Checks whether a queued message can be displayed, this is called for
+/// every processed event so only an atomic load is done when the queue is empty
+pub fn is_queue_ready() -> bool {
+    let ready = QUEUE_READY.load(Ordering::Acquire);
+    ready != 0 && clock_millis() >= ready
+}
+
+/// Displays the next queued message
+///
+/// # Safety
+///
+/// Must be called from the game thread
+pub unsafe fn display_queued() {
+    let ready = QUEUE_READY.load(Ordering::Acquire);
+
+    let Some(this) = first_instance_of::<USFXOnlineComponentUI>() else {
+        // Messages are kept until the message terminal exists, finding it scans
+        // every object so it's only retried after a delay. The queue may have
+        // been updated since the load in which case it's left as is
+        let retry = clock_millis().saturating_add(TERMINAL_RETRY.as_millis() as u64);
+        _ = QUEUE_READY.compare_exchange(ready, retry, Ordering::AcqRel, Ordering::Acquire);
+        return;
+    };
+
+    display_next(this);
+}
+
+/// Removes all the queued messages returning the number removed
+pub fn flush_queue() -> usize {
+    let mut queue = queue().lock();
+    let count = queue.flush().len();
+    update_queue_ready(&queue);
+    count
+}
+
+/// Displays the next queued message if the display interval has passed
+unsafe fn display_next(this: &mut USFXOnlineComponentUI) {
+    // Lock is released before displaying the message
+    let next = {
+        let mut queue = queue().lock();
+        let next = queue.pop_ready(Instant::now());
+        update_queue_ready(&queue);
+        next
+    };
+
+    let Some(next) = next else {
+        return;
+    };
+
+    let result = match &next.value {
+        PendingMessage::Notification(notification) => display_notification(this, notification),
+        PendingMessage::ConfirmationCode(code) => display_confirmation_code(this, code),
+    };
+
+    if let Err(err) = result {
+        error!("Failed to display system message: {}", err);
+    }
+}
+
+/// Updates [QUEUE_READY] from the queue
+fn update_queue_ready(queue: &NotificationQueue<PendingMessage>) {
+    let ready = queue
+        .ready_at(Instant::now())
+        .map(clock_millis_at)
+        .unwrap_or(0);
+    QUEUE_READY.store(ready, Ordering::Release);
+}
+
+/// Displays a notification in the message terminal, the notification is
+/// remembered so it can be displayed again if the terminal is cleared
+unsafe fn display_notification(
+    this: &mut USFXOnlineComponentUI,
+    message: &Notification,
+) -> Result<(), SdkError> {
+    show_notification(this, message)?;
+    record_displayed(message.clone());
+    Ok(())
+}
+
+/// Shows a notification in the message terminal, the event is called on
+/// the original function so the notification isn't seen by the hooks
+unsafe fn show_notification(
+    this: &mut USFXOnlineComponentUI,
+    message: &Notification,
+) -> Result<(), SdkError> {
+    // Strings are copied by the engine and freed once the event returns
+    let title = OwnedFString::new(&message.title);
+    let text = OwnedFString::new(&message.message);
+    let image = OwnedFString::new(&message.image);
+
+    // Send custom message instead
+    this.event_on_display_notification(FSFXOnlineMOTDInfo {
+        title: *title,
+        message: *text,
+        image: *image,
+        tracking_id: message.tracking_id,
+        priority: message.priority,
+        bw_ent_id: 0,
+        offer_id: 0,
+        ty: message.ty.into(),
+    })
+}
+
+/// Displays a confirmation code, the code is cleared from the terminal
+/// once it expires and acknowledged to the server once displayed. A new
+/// code replaces the expiry of any code already displayed
+unsafe fn display_confirmation_code(
+    this: &mut USFXOnlineComponentUI,
+    code: &ConfirmationCode,
+) -> Result<(), SdkError> {
+    if code.ttl == 0 {
+        warn!("Ignoring expired confirmation code {}", code.id);
+        return Ok(());
+    }
+
+    let notification = Notification {
+        title: CONFIRMATION_CODE_TITLE.to_string(),
+        message: confirmation_code_message(code),
+        image: String::new(),
+        ty: MessageType::MessageOfTheDay,
+        tracking_id: 0,
+        priority: 0,
+    };
+
+    // Codes aren't remembered so they're never displayed again
+    show_notification(this, &notification)?;
+
+    let expiry = clock_millis().saturating_add(code.ttl.saturating_mul(1000));
+    CODE_EXPIRY.store(expiry, Ordering::Release);
+
+    debug!("Displayed confirmation code {}", code.id);
+
+    if let Some(url) = &config().messages.ack_url {
+        ack::send(url, &code.id);
+    }
+
+    Ok(())
+}
+
+/// Creates the notification text for a confirmation code
+fn confirmation_code_message(code: &ConfirmationCode) -> String {
+    let minutes = code.ttl.div_ceil(60);
+    format!(
+        "Your confirmation code for {} is {}\n\nEnter it when setting a password for your account, \
+        the code expires in {} minute{}",
+        code.account_hint,
+        code.code,
+        minutes,
+        if minutes == 1 { "" } else { "s" }
+    )
+}
+
+/// Milliseconds since [CLOCK_START], never zero
+fn clock_millis() -> u64 {
+    clock_millis_at(Instant::now())
+}
+
+/// Milliseconds from [CLOCK_START] to the instant, never zero
+fn clock_millis_at(value: Instant) -> u64 {
+    let start = *CLOCK_START.get_or_init(Instant::now);
+    (value.saturating_duration_since(start).as_millis() as u64).max(1)
+}
+
+/// Checks whether the displayed confirmation code has expired, this is
+/// called for every processed event so only an atomic load is done when
+/// no code is displayed
+pub fn is_code_expired() -> bool {
+    let expiry = CODE_EXPIRY.load(Ordering::Acquire);
+    expiry != 0 && clock_millis() >= expiry
+}
+
+/// Removes the confirmation codes from the message terminal once the
+/// displayed code has expired. The terminal can only be cleared as a
+/// whole so the other displayed notifications are queued to be displayed
+/// again
+///
+/// # Safety
+///
+/// Must be called from the game thread
+pub unsafe fn clear_expired_code() {
+    let expiry = CODE_EXPIRY.load(Ordering::Acquire);
+    if expiry == 0 || clock_millis() < expiry {
+        return;
+    }
+
+    // Another code may have been displayed since the check
+    if CODE_EXPIRY
+        .compare_exchange(expiry, 0, Ordering::AcqRel, Ordering::Acquire)
+        .is_err()
+    {
+        return;
+    }
+
+    let Some(this) = first_instance_of::<USFXOnlineComponentUI>() else {
+        warn!("Unable to clear expired confirmation code, message terminal not found");
+        return;
+    };
+
+    // Clearing through the original function doesn't reach the hook
+    let restore = select_restore(std::mem::take(&mut *DISPLAYED.lock()));
+
+    if let Err(err) = this.clear_notifications() {
+        error!("Failed to clear expired confirmation code: {}", err);
+        return;
+    }
+
+    // Restored notifications aren't tracked by the queue so they aren't
+    // skipped as already displayed
+    let count = restore.len();
+    let mut queue = queue().lock();
+    for notification in restore {
+        let message = PendingMessage::Notification(notification);
+        if queue.push(RESTORE_PRIORITY, 0, message) == PushResult::Full {
+            warn!("Message queue is full, dropping restored notification");
+        }
+    }
+    update_queue_ready(&queue);
+
+    debug!(
+        "Cleared expired confirmation code, restoring {} notifications",
+        count
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn notification(title: &str) -> Notification {
+        Notification {
+            title: title.to_string(),
+            message: format!("{} message", title),
+            image: String::new(),
+            ty: MessageType::MessageOfTheDay,
+            tracking_id: 0,
+            priority: 0,
+        }
+    }
+
+    fn titles(notifications: &[Notification]) -> Vec<&str> {
+        notifications
+            .iter()
+            .map(|notification| notification.title.as_str())
+            .collect()
+    }
+
+    #[test]
+    fn restore_order() {
+        let mut displayed = VecDeque::new();
+        push_displayed(&mut displayed, notification("First"));
+        push_displayed(&mut displayed, notification(CONFIRMATION_CODE_TITLE));
+        push_displayed(&mut displayed, notification("Second"));
+
+        let restore = select_restore(displayed);
+        assert_eq!(titles(&restore), ["First", "Second"]);
+    }
+
+    #[test]
+    fn restore_limit() {
+        let mut displayed = VecDeque::new();
+        for index in 0..DISPLAYED_LIMIT + 8 {
+            push_displayed(&mut displayed, notification(&index.to_string()));
+        }
+        assert_eq!(displayed.len(), DISPLAYED_LIMIT);
+
+        // Oldest notifications are forgotten first
+        let restore = select_restore(displayed);
+        let expected: Vec<String> = (8..DISPLAYED_LIMIT + 8)
+            .map(|index| index.to_string())
+            .collect();
+        assert_eq!(titles(&restore), expected);
+    }
+
+    #[test]
+    fn restore_empty() {
+        assert!(select_restore(VecDeque::new()).is_empty());
+    }
+
+    #[test]
+    fn restore_queue_order() {
+        let mut queue = NotificationQueue::new(32, Duration::ZERO);
+        queue.push(0, 7, "tracked");
+        assert!(queue.pop_ready(Instant::now()).is_some());
+
+        // Restored after codes and before new messages, tracked notifications
+        // aren't skipped as already displayed
+        queue.push(RESTORE_PRIORITY, 0, "first");
+        queue.push(RESTORE_PRIORITY, 0, "tracked");
+        queue.push(RESTORE_PRIORITY, 0, "second");
+        queue.push(5, 0, "new");
+        queue.push(i32::MAX, 0, "code");
+
+        let order: Vec<&str> = std::iter::from_fn(|| queue.pop_ready(Instant::now()))
+            .map(|queued| queued.value)
+            .collect();
+        assert_eq!(order, ["code", "first", "tracked", "second", "new"]);
+    }
+}
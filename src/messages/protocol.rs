@@ -0,0 +1,608 @@
+//! Model for the system messages sent by the server, each message is a
+//! JSON envelope following the message prefix:
+//!
+//! ```json
+//! {"v": 1, "kind": "notification", "payload": {"title": "Title", "message": "Text"}}
+//! ```
+//!
+//! `v` is the protocol version, it's only increased for breaking changes.
+//! Adding optional fields or new kinds doesn't change the version, unknown
+//! fields are ignored and unknown kinds are reported as [ProtocolError::UnknownKind]
+//! so older plugins can skip them. The model is part of the plugin crate,
+//! outside of tests it only uses serde and serde_json so the server can
+//! keep a copy of this file in sync
+
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::{fmt::Display, str::FromStr};
+
+/// Current version of the message protocol
+pub const PROTOCOL_VERSION: u32 = 1;
+
+/// Envelope wrapping each system message
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Envelope {
+    /// Protocol version the message was created for
+    pub v: u32,
+    /// Message kind and payload
+    #[serde(flatten)]
+    pub message: Message,
+}
+
+/// System message kinds, serialized as the `kind` and `payload` fields
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "kind", content = "payload", rename_all = "snake_case")]
+pub enum Message {
+    /// Notification shown in the message terminal
+    Notification(Notification),
+    /// One-time confirmation code for an Origin account
+    ConfirmationCode(ConfirmationCode),
+    /// Sent by the plugin once a message has been displayed
+    Ack(Ack),
+}
+
+/// Notification shown in the message terminal
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Notification {
+    pub title: String,
+    pub message: String,
+    #[serde(default)]
+    pub image: String,
+    #[serde(default)]
+    pub ty: MessageType,
+    #[serde(default)]
+    pub tracking_id: i32,
+    #[serde(default)]
+    pub priority: i32,
+}
+
+/// One-time confirmation code allowing an Origin account to set a
+/// password, the code is never included in the debug output
+#[derive(Clone, PartialEq, Serialize, Deserialize)]
+pub struct ConfirmationCode {
+    /// Identifier of the code sent back in the [Ack]
+    pub id: String,
+    pub code: String,
+    /// Seconds until the code expires and is removed from the terminal
+    pub ttl: u64,
+    /// Hint for the account the code belongs to (e.g. "j***@example.com")
+    pub account_hint: String,
+}
+
+impl std::fmt::Debug for ConfirmationCode {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ConfirmationCode")
+            .field("id", &self.id)
+            .field("code", &"<redacted>")
+            .field("ttl", &self.ttl)
+            .field("account_hint", &self.account_hint)
+            .finish()
+    }
+}
+
+/// Acknowledgement that a message was displayed
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Ack {
+    /// Identifier of the displayed message
+    pub id: String,
+}
+
+/// Message type of a notification, matches the game
+/// `SFXOnlineFoundation.SFXOnlineConnection_MessageType` enum generated as
+/// `ESFXOnlineConnection_MessageType`
+///
+/// Serialized by name (e.g. "GAW_STATUS_UPDATE") and deserialized from
+/// either the name, with or without the "SFXONLINE_MT_" prefix, or the number
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[repr(u8)]
+pub enum MessageType {
+    #[default]
+    MessageOfTheDay = 0,
+    DownloadPrompt = 1,
+    GawSummary = 2,
+    GawStatusUpdate = 3,
+    FriendAchievement = 4,
+    FriendLeaderboardRankChange = 5,
+    MessageOfTheDayTickerOnly = 6,
+    DisconnectedTickerOnly = 7,
+    MpPromo = 8,
+}
+
+/// Value isn't a known message type
+#[derive(Debug)]
+pub struct InvalidMessageType(pub String);
+
+impl Display for InvalidMessageType {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Invalid message type \"{}\"", self.0)
+    }
+}
+
+impl std::error::Error for InvalidMessageType {}
+
+impl MessageType {
+    /// All the message types ordered by their value
+    pub const ALL: [MessageType; 9] = [
+        MessageType::MessageOfTheDay,
+        MessageType::DownloadPrompt,
+        MessageType::GawSummary,
+        MessageType::GawStatusUpdate,
+        MessageType::FriendAchievement,
+        MessageType::FriendLeaderboardRankChange,
+        MessageType::MessageOfTheDayTickerOnly,
+        MessageType::DisconnectedTickerOnly,
+        MessageType::MpPromo,
+    ];
+
+    /// Prefix of the names in the game enum
+    const PREFIX: &'static str = "SFXONLINE_MT_";
+
+    /// Name of the type within the game enum without the prefix
+    pub fn name(self) -> &'static str {
+        match self {
+            MessageType::MessageOfTheDay => "MESSAGEOFTHEDAY",
+            MessageType::DownloadPrompt => "DOWNLOAD_PROMPT",
+            MessageType::GawSummary => "GAW_SUMMARY",
+            MessageType::GawStatusUpdate => "GAW_STATUS_UPDATE",
+            // Misspelled in the game
+            MessageType::FriendAchievement => "FRIEND_ACHIVEMENT",
+            MessageType::FriendLeaderboardRankChange => "FRIEND_LEADERBOARD_RANK_CHANGE",
+            MessageType::MessageOfTheDayTickerOnly => "MESSAGEOFTHEDAY_TICKERONLY",
+            MessageType::DisconnectedTickerOnly => "DISCONNECTED_TICKERONLY",
+            MessageType::MpPromo => "MP_PROMO",
+        }
+    }
+}
+
+impl From<MessageType> for u8 {
+    fn from(value: MessageType) -> Self {
+        value as u8
+    }
+}
+
+impl TryFrom<u8> for MessageType {
+    type Error = InvalidMessageType;
+
+    fn try_from(value: u8) -> Result<Self, Self::Error> {
+        Self::ALL
+            .get(value as usize)
+            .copied()
+            .ok_or_else(|| InvalidMessageType(value.to_string()))
+    }
+}
+
+impl FromStr for MessageType {
+    type Err = InvalidMessageType;
+
+    /// Parses the name of the type, names are case insensitive
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let name = match s.get(..Self::PREFIX.len()) {
+            Some(prefix) if prefix.eq_ignore_ascii_case(Self::PREFIX) => &s[Self::PREFIX.len()..],
+            _ => s,
+        };
+
+        if name.eq_ignore_ascii_case("FRIEND_ACHIEVEMENT") {
+            return Ok(MessageType::FriendAchievement);
+        }
+
+        Self::ALL
+            .into_iter()
+            .find(|value| value.name().eq_ignore_ascii_case(name))
+            .ok_or_else(|| InvalidMessageType(s.to_string()))
+    }
+}
+
+impl Display for MessageType {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+impl Serialize for MessageType {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        serializer.serialize_str(self.name())
+    }
+}
+
+impl<'de> Deserialize<'de> for MessageType {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        #[derive(Deserialize)]
+        #[serde(untagged)]
+        enum Value {
+            Number(i64),
+            Name(String),
+        }
+
+        let value = match Value::deserialize(deserializer)? {
+            Value::Number(value) => u8::try_from(value)
+                .map_err(|_| InvalidMessageType(value.to_string()))
+                .and_then(MessageType::try_from),
+            Value::Name(value) => value.parse(),
+        };
+
+        value.map_err(serde::de::Error::custom)
+    }
+}
+
+/// Envelope before the payload is parsed
+#[derive(Deserialize)]
+struct RawEnvelope {
+    v: u32,
+    kind: String,
+    #[serde(default)]
+    payload: serde_json::Value,
+}
+
+#[derive(Debug)]
+pub enum ProtocolError {
+    /// Message isn't a valid envelope
+    Malformed(serde_json::Error),
+    /// Message version isn't supported by the plugin
+    UnsupportedVersion(u32),
+    /// Message kind isn't known by the plugin
+    UnknownKind(String),
+    /// Payload doesn't match the message kind
+    InvalidPayload(String, serde_json::Error),
+    /// Encoded message isn't valid base64 or UTF-8
+    InvalidEncoding,
+}
+
+impl Display for ProtocolError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ProtocolError::Malformed(err) => write!(f, "Malformed message: {}", err),
+            ProtocolError::UnsupportedVersion(version) => {
+                write!(f, "Unsupported message version {}", version)
+            }
+            ProtocolError::UnknownKind(kind) => write!(f, "Unknown message kind \"{}\"", kind),
+            ProtocolError::InvalidPayload(kind, err) => {
+                write!(f, "Invalid \"{}\" payload: {}", kind, err)
+            }
+            ProtocolError::InvalidEncoding => write!(f, "Invalid message encoding"),
+        }
+    }
+}
+
+impl std::error::Error for ProtocolError {}
+
+impl ProtocolError {
+    /// Describes the error without the serde error message, which can
+    /// include values from the payload such as confirmation codes
+    pub fn category(&self) -> String {
+        /// Name of the serde error category
+        fn category(err: &serde_json::Error) -> &'static str {
+            match err.classify() {
+                serde_json::error::Category::Io => "io error",
+                serde_json::error::Category::Syntax => "syntax error",
+                serde_json::error::Category::Data => "data error",
+                serde_json::error::Category::Eof => "unexpected end of input",
+            }
+        }
+
+        match self {
+            ProtocolError::Malformed(err) => format!("Malformed message ({})", category(err)),
+            ProtocolError::InvalidPayload(kind, err) => {
+                format!("Invalid \"{}\" payload ({})", kind, category(err))
+            }
+            err => err.to_string(),
+        }
+    }
+}
+
+impl Envelope {
+    /// Creates an envelope for the message using the current version
+    pub fn new(message: Message) -> Self {
+        Self {
+            v: PROTOCOL_VERSION,
+            message,
+        }
+    }
+
+    /// Decodes an envelope from its JSON, the JSON must only contain the envelope
+    pub fn decode(value: &str) -> Result<Self, ProtocolError> {
+        let raw: RawEnvelope = serde_json::from_str(value).map_err(ProtocolError::Malformed)?;
+        Self::from_raw(raw)
+    }
+
+    /// Decodes an envelope from an already parsed JSON value
+    pub fn from_value(value: serde_json::Value) -> Result<Self, ProtocolError> {
+        let raw: RawEnvelope = serde_json::from_value(value).map_err(ProtocolError::Malformed)?;
+        Self::from_raw(raw)
+    }
+
+    fn from_raw(raw: RawEnvelope) -> Result<Self, ProtocolError> {
+        if raw.v == 0 || raw.v > PROTOCOL_VERSION {
+            return Err(ProtocolError::UnsupportedVersion(raw.v));
+        }
+
+        let message = match raw.kind.as_str() {
+            "notification" => serde_json::from_value(raw.payload).map(Message::Notification),
+            "confirmation_code" => {
+                serde_json::from_value(raw.payload).map(Message::ConfirmationCode)
+            }
+            "ack" => serde_json::from_value(raw.payload).map(Message::Ack),
+            _ => return Err(ProtocolError::UnknownKind(raw.kind)),
+        }
+        .map_err(|err| ProtocolError::InvalidPayload(raw.kind, err))?;
+
+        Ok(Self { v: raw.v, message })
+    }
+
+    /// Encodes the envelope as JSON
+    pub fn encode(&self) -> String {
+        serde_json::to_string(self).expect("envelope serialization can't fail")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn notification() -> Notification {
+        Notification {
+            title: "Title".to_string(),
+            message: "Text".to_string(),
+            image: "image.dds".to_string(),
+            ty: MessageType::GawStatusUpdate,
+            tracking_id: 12,
+            priority: 3,
+        }
+    }
+
+    #[test]
+    fn round_trip() {
+        let messages = [
+            Message::Notification(notification()),
+            Message::ConfirmationCode(ConfirmationCode {
+                id: "code-1".to_string(),
+                code: "123456".to_string(),
+                ttl: 300,
+                account_hint: "j***@example.com".to_string(),
+            }),
+            Message::Ack(Ack {
+                id: "code-1".to_string(),
+            }),
+        ];
+
+        for message in messages {
+            let envelope = Envelope::new(message);
+            let decoded = Envelope::decode(&envelope.encode()).unwrap();
+            assert_eq!(decoded, envelope);
+
+            let value: serde_json::Value = serde_json::from_str(&envelope.encode()).unwrap();
+            assert_eq!(Envelope::from_value(value).unwrap(), envelope);
+        }
+    }
+
+    #[test]
+    fn encode_fields() {
+        let envelope = Envelope::new(Message::Notification(notification()));
+        let value: serde_json::Value = serde_json::from_str(&envelope.encode()).unwrap();
+        assert_eq!(
+            value,
+            json!({
+                "v": 1,
+                "kind": "notification",
+                "payload": {
+                    "title": "Title",
+                    "message": "Text",
+                    "image": "image.dds",
+                    "ty": "GAW_STATUS_UPDATE",
+                    "tracking_id": 12,
+                    "priority": 3,
+                }
+            })
+        );
+    }
+
+    #[test]
+    fn defaults_and_unknown_fields() {
+        let envelope = Envelope::decode(
+            r#"{"v": 1, "kind": "notification", "extra": true,
+                "payload": {"title": "Title", "message": "Text", "future": [1, 2]}}"#,
+        )
+        .unwrap();
+
+        assert_eq!(
+            envelope.message,
+            Message::Notification(Notification {
+                title: "Title".to_string(),
+                message: "Text".to_string(),
+                image: String::new(),
+                ty: MessageType::MessageOfTheDay,
+                tracking_id: 0,
+                priority: 0,
+            })
+        );
+    }
+
+    #[test]
+    fn message_type_values() {
+        let decode = |ty: serde_json::Value| {
+            let value = json!({
+                "v": 1,
+                "kind": "notification",
+                "payload": {"title": "", "message": "", "ty": ty},
+            });
+            match Envelope::from_value(value) {
+                Ok(Envelope {
+                    message: Message::Notification(notification),
+                    ..
+                }) => Ok(notification.ty),
+                Ok(envelope) => panic!("unexpected message {:?}", envelope),
+                Err(err) => Err(err),
+            }
+        };
+
+        assert_eq!(decode(json!(8)).unwrap(), MessageType::MpPromo);
+        assert_eq!(decode(json!("mp_promo")).unwrap(), MessageType::MpPromo);
+        assert_eq!(
+            decode(json!("SFXONLINE_MT_FRIEND_ACHIVEMENT")).unwrap(),
+            MessageType::FriendAchievement
+        );
+        assert_eq!(
+            decode(json!("FRIEND_ACHIEVEMENT")).unwrap(),
+            MessageType::FriendAchievement
+        );
+        assert!(matches!(
+            decode(json!(9)),
+            Err(ProtocolError::InvalidPayload(..))
+        ));
+        assert!(matches!(
+            decode(json!(-1)),
+            Err(ProtocolError::InvalidPayload(..))
+        ));
+        assert!(matches!(
+            decode(json!("UNKNOWN")),
+            Err(ProtocolError::InvalidPayload(..))
+        ));
+    }
+
+    /// Message types must match the generated game enum
+    #[test]
+    fn message_type_game_enum() {
+        use crate::sdk::sfxonlinefoundation::ESFXOnlineConnection_MessageType as GameType;
+
+        let game_types = [
+            GameType::SFXONLINE_MT_MESSAGEOFTHEDAY,
+            GameType::SFXONLINE_MT_DOWNLOAD_PROMPT,
+            GameType::SFXONLINE_MT_GAW_SUMMARY,
+            GameType::SFXONLINE_MT_GAW_STATUS_UPDATE,
+            GameType::SFXONLINE_MT_FRIEND_ACHIVEMENT,
+            GameType::SFXONLINE_MT_FRIEND_LEADERBOARD_RANK_CHANGE,
+            GameType::SFXONLINE_MT_MESSAGEOFTHEDAY_TICKERONLY,
+            GameType::SFXONLINE_MT_DISCONNECTED_TICKERONLY,
+            GameType::SFXONLINE_MT_MP_PROMO,
+        ];
+
+        assert_eq!(GameType::SFXONLINE_MT_MAX as usize, MessageType::ALL.len());
+
+        for game_type in game_types {
+            let value = MessageType::try_from(game_type as u8).unwrap();
+            assert_eq!(
+                format!("{}{}", MessageType::PREFIX, value.name()),
+                format!("{:?}", game_type)
+            );
+        }
+    }
+
+    #[test]
+    fn unsupported_version() {
+        assert!(matches!(
+            Envelope::decode(r#"{"v": 0, "kind": "ack", "payload": {"id": "1"}}"#),
+            Err(ProtocolError::UnsupportedVersion(0))
+        ));
+        assert!(matches!(
+            Envelope::decode(r#"{"v": 2, "kind": "ack", "payload": {"id": "1"}}"#),
+            Err(ProtocolError::UnsupportedVersion(2))
+        ));
+
+        // Version is checked before the kind
+        assert!(matches!(
+            Envelope::decode(r#"{"v": 2, "kind": "future"}"#),
+            Err(ProtocolError::UnsupportedVersion(2))
+        ));
+    }
+
+    #[test]
+    fn unknown_kind() {
+        match Envelope::decode(r#"{"v": 1, "kind": "future", "payload": {"id": "1"}}"#) {
+            Err(ProtocolError::UnknownKind(kind)) => assert_eq!(kind, "future"),
+            result => panic!("unexpected result {:?}", result),
+        }
+
+        // Payload is optional for unknown kinds
+        assert!(matches!(
+            Envelope::decode(r#"{"v": 1, "kind": "future"}"#),
+            Err(ProtocolError::UnknownKind(_))
+        ));
+    }
+
+    #[test]
+    fn invalid_payload() {
+        let values = [
+            r#"{"v": 1, "kind": "notification", "payload": {"title": "Title"}}"#,
+            r#"{"v": 1, "kind": "notification"}"#,
+            r#"{"v": 1, "kind": "confirmation_code", "payload": {"id": "1", "code": "2"}}"#,
+            r#"{"v": 1, "kind": "ack", "payload": {"id": 1}}"#,
+        ];
+
+        for value in values {
+            match Envelope::decode(value) {
+                Err(ProtocolError::InvalidPayload(..)) => {}
+                result => panic!("unexpected result for {}: {:?}", value, result),
+            }
+        }
+    }
+
+    #[test]
+    fn malformed() {
+        let values = [
+            "",
+            "not json",
+            r#"{"v": 1, "kind": "ack", "payload": {"id": "1"}"#,
+            r#"{"kind": "ack", "payload": {"id": "1"}}"#,
+            r#"{"v": 1, "payload": {"id": "1"}}"#,
+            r#"{"v": "1", "kind": "ack", "payload": {"id": "1"}}"#,
+            r#"{"v": -1, "kind": "ack", "payload": {"id": "1"}}"#,
+            r#"["v", 1]"#,
+            r#"{"v": 1, "kind": "ack", "payload": {"id": "1"}} trailing"#,
+        ];
+
+        for value in values {
+            match Envelope::decode(value) {
+                Err(ProtocolError::Malformed(_)) => {}
+                result => panic!("unexpected result for {:?}: {:?}", value, result),
+            }
+        }
+
+        assert!(matches!(
+            Envelope::from_value(json!("string")),
+            Err(ProtocolError::Malformed(_))
+        ));
+    }
+
+    #[test]
+    fn error_category() {
+        let value =
+            r#"{"v": 1, "kind": "confirmation_code", "payload": {"id": "1", "code": 847261}}"#;
+        let err = Envelope::decode(value).unwrap_err();
+        assert!(err.to_string().contains("847261"));
+        assert_eq!(
+            err.category(),
+            "Invalid \"confirmation_code\" payload (data error)"
+        );
+
+        let err =
+            Envelope::decode(r#"{"v": 1, "kind": "ack", "payload": {"id": "8472"#).unwrap_err();
+        assert_eq!(
+            err.category(),
+            "Malformed message (unexpected end of input)"
+        );
+
+        let err = Envelope::decode("not json").unwrap_err();
+        assert_eq!(err.category(), "Malformed message (syntax error)");
+
+        let err = Envelope::decode(r#"{"v": 1, "kind": "other"}"#).unwrap_err();
+        assert_eq!(err.category(), err.to_string());
+    }
+
+    #[test]
+    fn confirmation_code_redacted() {
+        let code = ConfirmationCode {
+            id: "code-1".to_string(),
+            code: "123456".to_string(),
+            ttl: 300,
+            account_hint: "j***@example.com".to_string(),
+        };
+        let debug = format!("{:?}", code);
+        assert!(!debug.contains("123456"));
+        assert!(debug.contains("<redacted>"));
+    }
+}
@@ -0,0 +1,358 @@
+//! Queue of notifications waiting to be displayed, notifications are
+//! displayed highest priority first with at least the display interval
+//! between each one. Notifications with the same non-zero tracking ID
+//! replace each other and aren't displayed again once shown
+
+use std::{
+    cmp::Ordering,
+    collections::VecDeque,
+    time::{Duration, Instant},
+};
+
+/// Number of displayed tracking IDs remembered to skip repeats
+const DISPLAYED_HISTORY: usize = 64;
+
+/// Outcome of pushing to the queue
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PushResult {
+    /// Notification was added to the queue
+    Queued,
+    /// Notification replaced a queued one with the same tracking ID
+    Replaced,
+    /// Notification with the same tracking ID was already displayed
+    Duplicate,
+    /// Queue is full of higher priority notifications
+    Full,
+}
+
+/// Notification waiting in the queue
+#[derive(Debug)]
+pub struct Queued<T> {
+    pub priority: i32,
+    /// Tracking ID of the notification, zero when it isn't tracked
+    pub tracking_id: i32,
+    pub value: T,
+    /// Order the notification was pushed, earlier is displayed first
+    sequence: u64,
+}
+
+/// Orders notifications by the order they're displayed in, higher
+/// priority first then the order they were pushed
+fn display_order<T>(a: &Queued<T>, b: &Queued<T>) -> Ordering {
+    b.priority
+        .cmp(&a.priority)
+        .then(a.sequence.cmp(&b.sequence))
+}
+
+pub struct NotificationQueue<T> {
+    entries: Vec<Queued<T>>,
+    capacity: usize,
+    /// Minimum time between displaying notifications
+    interval: Duration,
+    last_display: Option<Instant>,
+    /// Recently displayed tracking IDs, oldest first
+    displayed: VecDeque<i32>,
+    next_sequence: u64,
+}
+
+impl<T> NotificationQueue<T> {
+    pub const fn new(capacity: usize, interval: Duration) -> Self {
+        Self {
+            entries: Vec::new(),
+            capacity,
+            interval,
+            last_display: None,
+            displayed: VecDeque::new(),
+            next_sequence: 0,
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Adds a notification to the queue, when the queue is full the lowest
+    /// priority notification is dropped to make room
+    pub fn push(&mut self, priority: i32, tracking_id: i32, value: T) -> PushResult {
+        let entry = Queued {
+            priority,
+            tracking_id,
+            value,
+            sequence: self.next_sequence,
+        };
+        self.next_sequence += 1;
+
+        if tracking_id != 0 {
+            if self.displayed.contains(&tracking_id) {
+                return PushResult::Duplicate;
+            }
+
+            if let Some(existing) = self
+                .entries
+                .iter_mut()
+                .find(|existing| existing.tracking_id == tracking_id)
+            {
+                *existing = entry;
+                return PushResult::Replaced;
+            }
+        }
+
+        if self.entries.len() >= self.capacity {
+            match self.last_index() {
+                Some(index) if display_order(&entry, &self.entries[index]).is_lt() => {
+                    self.entries.swap_remove(index);
+                }
+                _ => return PushResult::Full,
+            }
+        }
+
+        self.entries.push(entry);
+        PushResult::Queued
+    }
+
+    /// Gets the time the next notification can be displayed, `now` when
+    /// nothing has been displayed yet and [None] when the queue is empty
+    pub fn ready_at(&self, now: Instant) -> Option<Instant> {
+        if self.entries.is_empty() {
+            return None;
+        }
+
+        Some(
+            self.last_display
+                .map_or(now, |last_display| last_display + self.interval),
+        )
+    }
+
+    /// Takes the next notification if the display interval has passed at `now`
+    pub fn pop_ready(&mut self, now: Instant) -> Option<Queued<T>> {
+        if self
+            .last_display
+            .is_some_and(|last_display| now < last_display + self.interval)
+        {
+            return None;
+        }
+
+        let index = self.first_index()?;
+        let entry = self.entries.swap_remove(index);
+
+        self.last_display = Some(now);
+        if entry.tracking_id != 0 {
+            if self.displayed.len() >= DISPLAYED_HISTORY {
+                self.displayed.pop_front();
+            }
+            self.displayed.push_back(entry.tracking_id);
+        }
+
+        Some(entry)
+    }
+
+    /// Gets the queued notifications in the order they will be displayed
+    pub fn iter(&self) -> impl Iterator<Item = &Queued<T>> {
+        let mut entries: Vec<&Queued<T>> = self.entries.iter().collect();
+        entries.sort_by(|a, b| display_order(a, b));
+        entries.into_iter()
+    }
+
+    /// Removes all the queued notifications returning them in display order
+    pub fn flush(&mut self) -> Vec<Queued<T>> {
+        let mut entries = std::mem::take(&mut self.entries);
+        entries.sort_by(display_order);
+        entries
+    }
+
+    /// Index of the notification to display first
+    fn first_index(&self) -> Option<usize> {
+        (0..self.entries.len()).min_by(|a, b| display_order(&self.entries[*a], &self.entries[*b]))
+    }
+
+    /// Index of the notification to display last
+    fn last_index(&self) -> Option<usize> {
+        (0..self.entries.len()).max_by(|a, b| display_order(&self.entries[*a], &self.entries[*b]))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const INTERVAL: Duration = Duration::from_secs(5);
+
+    /// Values of the queued notifications in display order
+    fn values(queue: &NotificationQueue<&'static str>) -> Vec<&'static str> {
+        queue.iter().map(|entry| entry.value).collect()
+    }
+
+    #[test]
+    fn priority_order() {
+        let mut queue = NotificationQueue::new(8, INTERVAL);
+        assert_eq!(queue.push(1, 0, "low"), PushResult::Queued);
+        assert_eq!(queue.push(10, 0, "high"), PushResult::Queued);
+        assert_eq!(queue.push(-5, 0, "lowest"), PushResult::Queued);
+        assert_eq!(queue.push(5, 0, "medium"), PushResult::Queued);
+
+        assert_eq!(queue.len(), 4);
+        assert_eq!(values(&queue), ["high", "medium", "low", "lowest"]);
+    }
+
+    #[test]
+    fn fifo_within_priority() {
+        let mut queue = NotificationQueue::new(8, INTERVAL);
+        queue.push(1, 0, "a");
+        queue.push(2, 0, "b");
+        queue.push(1, 0, "c");
+        queue.push(2, 0, "d");
+        queue.push(1, 0, "e");
+
+        assert_eq!(values(&queue), ["b", "d", "a", "c", "e"]);
+    }
+
+    #[test]
+    fn replaced() {
+        let mut queue = NotificationQueue::new(8, INTERVAL);
+        queue.push(1, 0, "a");
+        assert_eq!(queue.push(1, 7, "first"), PushResult::Queued);
+        queue.push(1, 0, "b");
+
+        // Replacement takes the new priority and position
+        assert_eq!(queue.push(1, 7, "second"), PushResult::Replaced);
+        assert_eq!(queue.len(), 3);
+        assert_eq!(values(&queue), ["a", "b", "second"]);
+
+        assert_eq!(queue.push(5, 7, "third"), PushResult::Replaced);
+        assert_eq!(values(&queue), ["third", "a", "b"]);
+
+        // Untracked notifications never replace each other
+        assert_eq!(queue.push(1, 0, "a"), PushResult::Queued);
+        assert_eq!(queue.len(), 4);
+    }
+
+    #[test]
+    fn duplicate() {
+        let now = Instant::now();
+        let mut queue = NotificationQueue::new(8, INTERVAL);
+        queue.push(1, 7, "first");
+        assert_eq!(queue.pop_ready(now).unwrap().value, "first");
+
+        assert_eq!(queue.push(1, 7, "again"), PushResult::Duplicate);
+        assert!(queue.is_empty());
+        assert_eq!(queue.push(1, 8, "other"), PushResult::Queued);
+    }
+
+    #[test]
+    fn duplicate_history_limit() {
+        let now = Instant::now();
+        let mut queue = NotificationQueue::new(DISPLAYED_HISTORY + 1, Duration::ZERO);
+
+        for tracking_id in 1..=DISPLAYED_HISTORY as i32 + 1 {
+            queue.push(0, tracking_id, "value");
+            assert!(queue.pop_ready(now).is_some());
+        }
+
+        // Oldest tracking ID is forgotten
+        assert_eq!(queue.push(0, 1, "value"), PushResult::Queued);
+        assert_eq!(queue.push(0, 2, "value"), PushResult::Duplicate);
+    }
+
+    #[test]
+    fn full_drops_lowest() {
+        let mut queue = NotificationQueue::new(3, INTERVAL);
+        queue.push(5, 0, "a");
+        queue.push(1, 0, "b");
+        queue.push(3, 0, "c");
+
+        // Higher priority than the lowest replaces it
+        assert_eq!(queue.push(2, 0, "d"), PushResult::Queued);
+        assert_eq!(values(&queue), ["a", "c", "d"]);
+
+        // Equal priority is displayed after the queued ones so it's dropped
+        assert_eq!(queue.push(2, 0, "e"), PushResult::Full);
+        assert_eq!(queue.push(0, 0, "f"), PushResult::Full);
+        assert_eq!(values(&queue), ["a", "c", "d"]);
+
+        // Latest of the lowest priority is dropped first
+        assert_eq!(queue.push(3, 0, "g"), PushResult::Queued);
+        assert_eq!(queue.push(4, 0, "h"), PushResult::Queued);
+        assert_eq!(values(&queue), ["a", "h", "c"]);
+    }
+
+    #[test]
+    fn full_replaces_tracked() {
+        let mut queue = NotificationQueue::new(2, INTERVAL);
+        queue.push(1, 7, "a");
+        queue.push(1, 0, "b");
+
+        // Replacing doesn't need space in the queue
+        assert_eq!(queue.push(0, 7, "c"), PushResult::Replaced);
+        assert_eq!(values(&queue), ["b", "c"]);
+    }
+
+    #[test]
+    fn interval() {
+        let now = Instant::now();
+        let mut queue = NotificationQueue::new(8, INTERVAL);
+        assert_eq!(queue.ready_at(now), None);
+        assert!(queue.pop_ready(now).is_none());
+
+        queue.push(1, 0, "a");
+        queue.push(1, 0, "b");
+
+        // Ready immediately when nothing has been displayed
+        assert_eq!(queue.ready_at(now), Some(now));
+        assert_eq!(queue.pop_ready(now).unwrap().value, "a");
+
+        let next = now + INTERVAL;
+        assert_eq!(queue.ready_at(now), Some(next));
+        assert!(queue.pop_ready(next - Duration::from_millis(1)).is_none());
+        assert_eq!(queue.len(), 1);
+
+        assert_eq!(queue.pop_ready(next).unwrap().value, "b");
+        assert_eq!(queue.ready_at(next), None);
+
+        // Interval is kept from the last display once the queue is refilled
+        queue.push(1, 0, "c");
+        assert_eq!(queue.ready_at(next), Some(next + INTERVAL));
+        assert!(queue.pop_ready(next).is_none());
+        assert_eq!(queue.pop_ready(next + INTERVAL * 2).unwrap().value, "c");
+    }
+
+    #[test]
+    fn pop_order() {
+        let now = Instant::now();
+        let mut queue = NotificationQueue::new(8, Duration::ZERO);
+        queue.push(1, 0, "a");
+        queue.push(3, 0, "b");
+        queue.push(1, 0, "c");
+        queue.push(2, 0, "d");
+
+        let popped: Vec<&str> = std::iter::from_fn(|| queue.pop_ready(now))
+            .map(|entry| entry.value)
+            .collect();
+        assert_eq!(popped, ["b", "d", "a", "c"]);
+    }
+
+    #[test]
+    fn flush() {
+        let mut queue = NotificationQueue::new(8, INTERVAL);
+        queue.push(1, 0, "a");
+        queue.push(3, 4, "b");
+        queue.push(1, 0, "c");
+        queue.push(2, 0, "d");
+
+        let flushed = queue.flush();
+        let values: Vec<&str> = flushed.iter().map(|entry| entry.value).collect();
+        assert_eq!(values, ["b", "d", "a", "c"]);
+        assert_eq!(flushed[0].priority, 3);
+        assert_eq!(flushed[0].tracking_id, 4);
+
+        assert!(queue.is_empty());
+        assert_eq!(queue.ready_at(Instant::now()), None);
+        assert!(queue.flush().is_empty());
+
+        // Flushed notifications weren't displayed so they can be queued again
+        assert_eq!(queue.push(3, 4, "b"), PushResult::Queued);
+    }
+}
@@ -0,0 +1,558 @@
+//! Signature scanning for locating functions and globals within the
+//! game executable, the scanning itself operates on byte slices so it
+//! doesn't depend on the memory of the running process.
+
+use serde::{Deserialize, Serialize};
+use std::{fmt::Display, str::FromStr};
+use windows_sys::Win32::System::LibraryLoader::GetModuleHandleW;
+
+/// Section characteristics flag for executable sections
+const IMAGE_SCN_MEM_EXECUTE: u32 = 0x20000000;
+
+/// Error from parsing a pattern string
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PatternError {
+    /// Pattern contained no bytes
+    Empty,
+    /// Pattern contained a token that isn't a hex byte or wildcard
+    InvalidToken(String),
+}
+
+impl Display for PatternError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            PatternError::Empty => f.write_str("pattern is empty"),
+            PatternError::InvalidToken(token) => write!(f, "invalid pattern token \"{}\"", token),
+        }
+    }
+}
+
+impl std::error::Error for PatternError {}
+
+/// Byte pattern with wildcards, parsed from IDA style strings
+/// such as "8B 4C 24 ?? 56"
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Pattern {
+    bytes: Vec<Option<u8>>,
+}
+
+impl FromStr for Pattern {
+    type Err = PatternError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let bytes = s
+            .split_whitespace()
+            .map(|token| match token {
+                "?" | "??" => Ok(None),
+                token if token.len() == 2 => u8::from_str_radix(token, 16)
+                    .map(Some)
+                    .map_err(|_| PatternError::InvalidToken(token.to_string())),
+                token => Err(PatternError::InvalidToken(token.to_string())),
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+
+        if bytes.is_empty() {
+            return Err(PatternError::Empty);
+        }
+
+        Ok(Self { bytes })
+    }
+}
+
+impl Pattern {
+    /// Checks if the pattern matches the start of the provided data
+    pub fn matches(&self, data: &[u8]) -> bool {
+        data.len() >= self.bytes.len()
+            && self
+                .bytes
+                .iter()
+                .zip(data)
+                .all(|(pattern, value)| pattern.map_or(true, |pattern| pattern == *value))
+    }
+
+    /// Iterates the offsets of all the matches within the data
+    pub fn find_all<'a>(&'a self, data: &'a [u8]) -> impl Iterator<Item = usize> + 'a {
+        let end = (data.len() + 1).saturating_sub(self.bytes.len());
+        (0..end).filter(move |offset| self.matches(&data[*offset..]))
+    }
+}
+
+/// How the address of a match is turned into the resulting address
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Deref {
+    /// Use the address of the match
+    #[default]
+    None,
+    /// Read a 32bit absolute address at the match
+    Absolute,
+    /// Read a 32bit displacement at the match relative to the end of the
+    /// displacement (Target of a call or jmp rel32)
+    Relative,
+}
+
+/// Signature describing how to locate an address
+#[derive(Debug, Clone)]
+pub struct Signature {
+    /// Pattern to search for
+    pub pattern: Pattern,
+    /// Offset from the start of the match to apply before dereferencing
+    pub offset: isize,
+    /// Dereference to apply to the matched address
+    pub deref: Deref,
+}
+
+/// Error from resolving a signature
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ScanError {
+    /// Pattern wasn't found
+    NotFound,
+    /// Pattern matches resolved to more than one address
+    Ambiguous(usize),
+    /// Offset or dereference pointed outside the scanned data
+    OutOfBounds,
+}
+
+impl Display for ScanError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ScanError::NotFound => f.write_str("pattern not found"),
+            ScanError::Ambiguous(count) => {
+                write!(f, "pattern resolved to {} different addresses", count)
+            }
+            ScanError::OutOfBounds => f.write_str("signature offset out of bounds"),
+        }
+    }
+}
+
+impl std::error::Error for ScanError {}
+
+/// Reads a little endian u32 at the offset within the data
+fn read_u32(data: &[u8], offset: usize) -> Option<u32> {
+    let bytes = data.get(offset..offset + 4)?;
+    Some(u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+}
+
+impl Signature {
+    pub fn new(pattern: &str, offset: isize, deref: Deref) -> Result<Self, PatternError> {
+        Ok(Self {
+            pattern: pattern.parse()?,
+            offset,
+            deref,
+        })
+    }
+
+    /// Resolves the signature against the provided data which is
+    /// located at the `base` address. Every match must resolve to the
+    /// same address, matches that resolve to different addresses are
+    /// ambiguous.
+    pub fn resolve(&self, data: &[u8], base: usize) -> Result<usize, ScanError> {
+        single_address(self.resolve_all(data, base)?)
+    }
+
+    /// Resolves each of the matches of the signature against the provided
+    /// data which is located at the `base` address. The addresses are
+    /// sorted with duplicates removed.
+    pub fn resolve_all(&self, data: &[u8], base: usize) -> Result<Vec<usize>, ScanError> {
+        let mut addresses = self
+            .pattern
+            .find_all(data)
+            .map(|offset| self.resolve_match(data, base, offset))
+            .collect::<Result<Vec<_>, _>>()?;
+        addresses.sort_unstable();
+        addresses.dedup();
+        Ok(addresses)
+    }
+
+    /// Resolves the address for the match at `offset` within the data
+    fn resolve_match(&self, data: &[u8], base: usize, offset: usize) -> Result<usize, ScanError> {
+        let offset = offset
+            .checked_add_signed(self.offset)
+            .ok_or(ScanError::OutOfBounds)?;
+
+        match self.deref {
+            Deref::None => {
+                if offset >= data.len() {
+                    return Err(ScanError::OutOfBounds);
+                }
+                Ok(base + offset)
+            }
+            Deref::Absolute => read_u32(data, offset)
+                .map(|value| value as usize)
+                .ok_or(ScanError::OutOfBounds),
+            Deref::Relative => read_u32(data, offset)
+                .map(|value| (base + offset + 4).wrapping_add_signed(value as i32 as isize))
+                .ok_or(ScanError::OutOfBounds),
+        }
+    }
+}
+
+/// Gets the only address from the resolved addresses
+fn single_address(addresses: Vec<usize>) -> Result<usize, ScanError> {
+    match addresses.as_slice() {
+        [] => Err(ScanError::NotFound),
+        [address] => Ok(*address),
+        _ => Err(ScanError::Ambiguous(addresses.len())),
+    }
+}
+
+/// Section from the image of a module
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Section {
+    /// Offset of the section relative to the image base
+    pub virtual_address: usize,
+    /// Size of the section in memory
+    pub virtual_size: usize,
+    /// Section characteristics flags
+    pub characteristics: u32,
+}
+
+impl Section {
+    pub fn is_executable(&self) -> bool {
+        self.characteristics & IMAGE_SCN_MEM_EXECUTE != 0
+    }
+}
+
+/// Parses the section headers from the PE headers at the start of
+/// a mapped image. Returns [None] if the headers are invalid.
+pub fn parse_sections(headers: &[u8]) -> Option<Vec<Section>> {
+    // DOS header "MZ" signature and offset to the NT headers
+    if headers.get(0..2)? != b"MZ" {
+        return None;
+    }
+    let nt_offset = read_u32(headers, 0x3C)? as usize;
+
+    // NT headers "PE\0\0" signature
+    if headers.get(nt_offset..nt_offset + 4)? != b"PE\0\0" {
+        return None;
+    }
+
+    let file_header = nt_offset + 4;
+    let section_count = u16::from_le_bytes(
+        headers
+            .get(file_header + 2..file_header + 4)?
+            .try_into()
+            .ok()?,
+    ) as usize;
+    let optional_header_size = u16::from_le_bytes(
+        headers
+            .get(file_header + 16..file_header + 18)?
+            .try_into()
+            .ok()?,
+    ) as usize;
+
+    // File header is 20 bytes followed by the optional header
+    let sections_start = file_header + 20 + optional_header_size;
+
+    (0..section_count)
+        .map(|index| {
+            let start = sections_start + index * 40;
+            let header = headers.get(start..start + 40)?;
+
+            Some(Section {
+                virtual_size: read_u32(header, 8)? as usize,
+                virtual_address: read_u32(header, 12)? as usize,
+                characteristics: read_u32(header, 36)?,
+            })
+        })
+        .collect()
+}
+
+/// Size of the PE headers page read when parsing the main module
+const HEADERS_SIZE: usize = 0x1000;
+
+/// Main game executable module loaded in memory
+pub struct Module {
+    /// Base address the module is loaded at
+    pub base: usize,
+    /// Sections within the module image
+    pub sections: Vec<Section>,
+}
+
+impl Module {
+    /// Obtains the main executable module of the current process
+    ///
+    /// # Safety
+    ///
+    /// Reads the PE headers from the memory of the current process
+    pub unsafe fn main() -> Option<Self> {
+        let base = GetModuleHandleW(std::ptr::null()) as usize;
+        if base == 0 {
+            return None;
+        }
+
+        let headers = std::slice::from_raw_parts(base as *const u8, HEADERS_SIZE);
+        let sections = parse_sections(headers)?;
+
+        Some(Self { base, sections })
+    }
+
+    /// Gets the memory of the provided section
+    ///
+    /// # Safety
+    ///
+    /// The section must belong to this module
+    pub unsafe fn section_data(&self, section: &Section) -> &[u8] {
+        std::slice::from_raw_parts(
+            (self.base + section.virtual_address) as *const u8,
+            section.virtual_size,
+        )
+    }
+
+    /// Checks whether the address lies within one of the module sections
+    pub fn contains(&self, address: usize) -> bool {
+        self.sections.iter().any(|section| {
+            let start = self.base + section.virtual_address;
+            address >= start && address < start + section.virtual_size
+        })
+    }
+
+    /// Resolves the signature by scanning each of the executable
+    /// sections of the module, every match must resolve to the same address
+    ///
+    /// # Safety
+    ///
+    /// Reads the section memory of the current process
+    pub unsafe fn resolve(&self, signature: &Signature) -> Result<usize, ScanError> {
+        single_address(self.resolve_all(signature)?)
+    }
+
+    /// Resolves every match of the signature within the executable
+    /// sections of the module, sorted with duplicates removed
+    ///
+    /// # Safety
+    ///
+    /// Reads the section memory of the current process
+    pub unsafe fn resolve_all(&self, signature: &Signature) -> Result<Vec<usize>, ScanError> {
+        let mut addresses = Vec::new();
+
+        for section in self
+            .sections
+            .iter()
+            .filter(|section| section.is_executable())
+        {
+            let data = self.section_data(section);
+            let base = self.base + section.virtual_address;
+            addresses.extend(signature.resolve_all(data, base)?);
+        }
+
+        addresses.sort_unstable();
+        addresses.dedup();
+        Ok(addresses)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn pattern_parse() {
+        let pattern: Pattern = "8B ?? 4c ? 56".parse().unwrap();
+        assert_eq!(
+            pattern.bytes,
+            [Some(0x8B), None, Some(0x4C), None, Some(0x56)]
+        );
+
+        assert_eq!("".parse::<Pattern>(), Err(PatternError::Empty));
+        assert_eq!("   ".parse::<Pattern>(), Err(PatternError::Empty));
+        assert_eq!(
+            "8B GG".parse::<Pattern>(),
+            Err(PatternError::InvalidToken("GG".to_string()))
+        );
+        assert_eq!(
+            "8B4C".parse::<Pattern>(),
+            Err(PatternError::InvalidToken("8B4C".to_string()))
+        );
+        assert_eq!(
+            "8B ???".parse::<Pattern>(),
+            Err(PatternError::InvalidToken("???".to_string()))
+        );
+    }
+
+    #[test]
+    fn pattern_wildcards() {
+        let pattern: Pattern = "8B ?? 56".parse().unwrap();
+        assert!(pattern.matches(&[0x8B, 0x00, 0x56]));
+        assert!(pattern.matches(&[0x8B, 0xFF, 0x56, 0x90]));
+        assert!(!pattern.matches(&[0x8B, 0x00, 0x57]));
+        assert!(!pattern.matches(&[0x8B, 0x00]));
+
+        let data = [0x8B, 0x01, 0x56, 0x8B, 0x02, 0x56, 0x8B, 0x03];
+        assert_eq!(pattern.find_all(&data).collect::<Vec<_>>(), [0, 3]);
+    }
+
+    #[test]
+    fn resolve_not_found() {
+        let signature = Signature::new("AA BB", 0, Deref::None).unwrap();
+        assert_eq!(
+            signature.resolve(&[0xAA, 0xCC, 0xBB], 0x1000),
+            Err(ScanError::NotFound)
+        );
+        assert_eq!(signature.resolve(&[0xAA], 0x1000), Err(ScanError::NotFound));
+    }
+
+    #[test]
+    fn resolve_ambiguous() {
+        let signature = Signature::new("AA ??", 0, Deref::None).unwrap();
+        let data = [0xAA, 0x01, 0xAA, 0x02, 0xAA, 0x03];
+        assert_eq!(
+            signature.resolve(&data, 0x1000),
+            Err(ScanError::Ambiguous(3))
+        );
+        assert_eq!(
+            signature.resolve_all(&data, 0x1000),
+            Ok(vec![0x1000, 0x1002, 0x1004])
+        );
+    }
+
+    #[test]
+    fn resolve_same_address() {
+        // Both matches load the same absolute address
+        let signature = Signature::new("8B 0D ?? ?? ?? ?? C3", 2, Deref::Absolute).unwrap();
+        let data = [
+            0x8B, 0x0D, 0x34, 0x12, 0x00, 0x00, 0xC3, //
+            0x8B, 0x0D, 0x34, 0x12, 0x00, 0x00, 0xC3,
+        ];
+        assert_eq!(signature.resolve(&data, 0x1000), Ok(0x1234));
+
+        let data = [
+            0x8B, 0x0D, 0x34, 0x12, 0x00, 0x00, 0xC3, //
+            0x8B, 0x0D, 0x78, 0x56, 0x00, 0x00, 0xC3,
+        ];
+        assert_eq!(
+            signature.resolve(&data, 0x1000),
+            Err(ScanError::Ambiguous(2))
+        );
+    }
+
+    #[test]
+    fn resolve_offset() {
+        let data = [0x90, 0x90, 0xAA, 0xBB, 0x90];
+
+        let signature = Signature::new("AA BB", 1, Deref::None).unwrap();
+        assert_eq!(signature.resolve(&data, 0x1000), Ok(0x1003));
+
+        // Negative offset to the start of the function before the match
+        let signature = Signature::new("AA BB", -2, Deref::None).unwrap();
+        assert_eq!(signature.resolve(&data, 0x1000), Ok(0x1000));
+
+        let signature = Signature::new("AA BB", -3, Deref::None).unwrap();
+        assert_eq!(
+            signature.resolve(&data, 0x1000),
+            Err(ScanError::OutOfBounds)
+        );
+
+        let signature = Signature::new("AA BB", 3, Deref::None).unwrap();
+        assert_eq!(
+            signature.resolve(&data, 0x1000),
+            Err(ScanError::OutOfBounds)
+        );
+    }
+
+    #[test]
+    fn resolve_absolute() {
+        // mov ecx, [0x01AB5634]
+        let data = [0x90, 0x8B, 0x0D, 0x34, 0x56, 0xAB, 0x01, 0x90];
+        let signature = Signature::new("8B 0D ?? ?? ?? ??", 2, Deref::Absolute).unwrap();
+        assert_eq!(signature.resolve(&data, 0x1000), Ok(0x01AB5634));
+
+        // Not enough bytes for the address
+        let signature = Signature::new("8B 0D", 4, Deref::Absolute).unwrap();
+        assert_eq!(
+            signature.resolve(&data, 0x1000),
+            Err(ScanError::OutOfBounds)
+        );
+    }
+
+    #[test]
+    fn resolve_relative() {
+        // call +0x10 at 0x1001, relative to the end of the call at 0x1006
+        let data = [0x90, 0xE8, 0x10, 0x00, 0x00, 0x00, 0x90];
+        let signature = Signature::new("E8 ?? ?? ?? ??", 1, Deref::Relative).unwrap();
+        assert_eq!(signature.resolve(&data, 0x1000), Ok(0x1016));
+
+        // call -0x10
+        let data = [0x90, 0xE8, 0xF0, 0xFF, 0xFF, 0xFF, 0x90];
+        assert_eq!(signature.resolve(&data, 0x1000), Ok(0x0FF6));
+    }
+
+    /// Creates PE headers with the provided sections
+    fn pe_headers(sections: &[(u32, u32, u32)]) -> Vec<u8> {
+        let mut headers = vec![0u8; 0x400];
+        headers[0..2].copy_from_slice(b"MZ");
+        headers[0x3C..0x40].copy_from_slice(&0x80u32.to_le_bytes());
+        headers[0x80..0x84].copy_from_slice(b"PE\0\0");
+
+        // File header section count and optional header size
+        let file_header = 0x84;
+        headers[file_header + 2..file_header + 4]
+            .copy_from_slice(&(sections.len() as u16).to_le_bytes());
+        headers[file_header + 16..file_header + 18].copy_from_slice(&0xE0u16.to_le_bytes());
+
+        let sections_start = file_header + 20 + 0xE0;
+        for (index, (virtual_address, virtual_size, characteristics)) in sections.iter().enumerate()
+        {
+            let start = sections_start + index * 40;
+            headers[start + 8..start + 12].copy_from_slice(&virtual_size.to_le_bytes());
+            headers[start + 12..start + 16].copy_from_slice(&virtual_address.to_le_bytes());
+            headers[start + 36..start + 40].copy_from_slice(&characteristics.to_le_bytes());
+        }
+
+        headers
+    }
+
+    #[test]
+    fn parse_sections_headers() {
+        let headers = pe_headers(&[(0x1000, 0x5000, 0x60000020), (0x6000, 0x800, 0xC0000040)]);
+        let sections = parse_sections(&headers).unwrap();
+
+        assert_eq!(
+            sections,
+            [
+                Section {
+                    virtual_address: 0x1000,
+                    virtual_size: 0x5000,
+                    characteristics: 0x60000020,
+                },
+                Section {
+                    virtual_address: 0x6000,
+                    virtual_size: 0x800,
+                    characteristics: 0xC0000040,
+                },
+            ]
+        );
+        assert!(sections[0].is_executable());
+        assert!(!sections[1].is_executable());
+
+        let module = Module {
+            base: 0x400000,
+            sections,
+        };
+        assert!(module.contains(0x401000));
+        assert!(module.contains(0x405FFF));
+        assert!(!module.contains(0x406800));
+        assert!(!module.contains(0x400000));
+    }
+
+    #[test]
+    fn parse_sections_invalid() {
+        let valid = pe_headers(&[(0x1000, 0x5000, 0x60000020)]);
+
+        let mut headers = valid.clone();
+        headers[0] = b'X';
+        assert_eq!(parse_sections(&headers), None);
+
+        let mut headers = valid.clone();
+        headers[0x80..0x84].copy_from_slice(b"NE\0\0");
+        assert_eq!(parse_sections(&headers), None);
+
+        // NT headers offset past the end of the headers
+        let mut headers = valid.clone();
+        headers[0x3C..0x40].copy_from_slice(&0x1000u32.to_le_bytes());
+        assert_eq!(parse_sections(&headers), None);
+
+        // Section table truncated
+        assert_eq!(parse_sections(&valid[..0x190]), None);
+    }
+}
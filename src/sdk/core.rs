@@ -1,32 +1,253 @@
+use super::{layout::assert_layout, memory};
+use parking_lot::Mutex;
 use std::{
-    char::decode_utf16,
+    borrow::Cow,
+    char::{decode_utf16, REPLACEMENT_CHARACTER},
+    collections::HashMap,
     ffi::CStr,
-    fmt::{Debug, Display},
+    fmt::{Debug, Display, Write},
     marker::PhantomData,
+    mem::ManuallyDrop,
+    ops::{Deref, DerefMut, Index, IndexMut},
     os::raw::{c_char, c_int, c_uchar, c_uint, c_ulong, c_ushort, c_void},
+    string::FromUtf16Error,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    },
 };
 
-/// Static memory address for the game objects
-static GAME_OBJECT_OFFSET: u32 = 0x01AB5634;
+/// Memory address for the game objects, resolved at startup
+static GAME_OBJECTS_ADDRESS: AtomicUsize = AtomicUsize::new(0);
+
+/// Memory address for the game names, only set when configured
+static GAME_NAMES_ADDRESS: AtomicUsize = AtomicUsize::new(0);
 
 type GameObjectsArray = TArray<*mut UObject>;
 
+type GameNamesArray = TArray<*mut FNameEntry>;
+
+/// Sets the memory address of the game objects array
+pub fn set_game_objects_address(address: usize) {
+    GAME_OBJECTS_ADDRESS.store(address, Ordering::Release);
+}
+
 /// Obtains a reference to the [TArray] containing the game objects
 pub fn game_objects_ref() -> &'static mut TArray<*mut UObject> {
+    let address = GAME_OBJECTS_ADDRESS.load(Ordering::Acquire);
     unsafe {
-        (GAME_OBJECT_OFFSET as *const GameObjectsArray as *mut GameObjectsArray)
+        (address as *mut GameObjectsArray)
             .as_mut()
             .expect("Game objects pointer was null")
     }
 }
 
+/// Sets the memory address of the game names array
+pub fn set_game_names_address(address: usize) {
+    GAME_NAMES_ADDRESS.store(address, Ordering::Release);
+}
+
+/// Obtains the game names array, [None] if the address hasn't been resolved
+fn try_game_names_ref() -> Option<&'static GameNamesArray> {
+    let address = GAME_NAMES_ADDRESS.load(Ordering::Acquire);
+    unsafe { (address as *const GameNamesArray).as_ref() }
+}
+
+/// Obtains the game objects array, [None] if the address hasn't been resolved
+fn try_game_objects_ref() -> Option<&'static GameObjectsArray> {
+    let address = GAME_OBJECTS_ADDRESS.load(Ordering::Acquire);
+    unsafe { (address as *const GameObjectsArray).as_ref() }
+}
+
 pub fn get_function_object(index: usize) -> Option<*mut UFunction> {
-    let fn_object = *game_objects_ref().get(index)?;
+    let fn_object = *try_game_objects_ref()?.get(index)?;
     let fn_ptr = fn_object.cast::<UFunction>() as *mut _;
     Some(fn_ptr)
 }
 
-/// Array type
+/// Iterator over the live objects within the game objects array,
+/// null entries are skipped
+pub struct GameObjects {
+    index: usize,
+}
+
+impl Iterator for GameObjects {
+    type Item = &'static UObject;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let game_objects = try_game_objects_ref()?;
+
+        // Length is checked on each step as objects can be loaded while iterating
+        while let Some(object) = game_objects.get(self.index) {
+            self.index += 1;
+
+            if let Some(object) = unsafe { object.as_ref() } {
+                return Some(object);
+            }
+        }
+
+        None
+    }
+}
+
+/// Iterates all the live game objects, empty if the game objects
+/// address hasn't been resolved
+pub fn objects() -> GameObjects {
+    GameObjects { index: 0 }
+}
+
+/// Iterates the objects that are an instance of the class with the
+/// provided name (e.g. "SFXOnlineComponentUI") or one of its sub classes
+pub fn objects_of_class(class_name: &str) -> impl Iterator<Item = &'static UObject> + '_ {
+    objects().filter(move |object| object.is_a_named(class_name))
+}
+
+/// Finds the object with the provided full name
+/// (e.g. "Class SFXGame.SFXOnlineComponentUI")
+///
+/// # Safety
+///
+/// `T` must be the class of the object or one of its super classes
+pub unsafe fn find_object<T>(full_name: &str) -> Option<&'static mut T> {
+    // Compare the short name first to avoid building every full name
+    let name = full_name.rsplit(['.', ' ']).next()?;
+
+    objects()
+        .filter(|object| object.name.matches(name))
+        .find(|object| object.get_full_name() == full_name)
+        .and_then(|object| (object as *const UObject as *mut T).as_mut())
+}
+
+/// Finds the first instance of `T` that isn't a class default object
+pub fn first_instance_of<T: StaticClass>() -> Option<&'static mut T> {
+    objects_of_class(T::NAME)
+        .find(|object| !object.is_default_object())
+        .and_then(|object| unsafe { (object as *const UObject as *mut T).as_mut() })
+}
+
+/// Rust type for a game class
+///
+/// # Safety
+///
+/// The implementing type must match the layout of the class
+/// named [StaticClass::NAME]
+pub unsafe trait StaticClass {
+    /// Name of the class (e.g. "SFXOnlineComponentUI")
+    const NAME: &'static str;
+}
+
+unsafe impl StaticClass for UObject {
+    const NAME: &'static str = "Object";
+}
+
+unsafe impl StaticClass for UClass {
+    const NAME: &'static str = "Class";
+}
+
+unsafe impl StaticClass for UFunction {
+    const NAME: &'static str = "Function";
+}
+
+/// Errors from looking up game objects
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SdkError {
+    /// Game objects address hasn't been resolved
+    MissingGameObjects,
+    /// No function object exists with the full name
+    FunctionNotFound(String),
+    /// Game names address hasn't been resolved
+    MissingGameNames,
+    /// No name entry exists for the name
+    NameNotFound(String),
+    /// Engine memory can't be allocated without the game allocator
+    MissingGameAllocator,
+}
+
+impl Display for SdkError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SdkError::MissingGameObjects => f.write_str("game objects address not resolved"),
+            SdkError::FunctionNotFound(name) => write!(f, "missing function object \"{}\"", name),
+            SdkError::MissingGameNames => f.write_str("game names address not resolved"),
+            SdkError::NameNotFound(name) => write!(f, "missing name entry \"{}\"", name),
+            SdkError::MissingGameAllocator => f.write_str("game allocator not available"),
+        }
+    }
+}
+
+impl std::error::Error for SdkError {}
+
+/// Cache of addresses keyed by name, built by walking one of the game arrays
+struct LookupCache {
+    /// Addresses keyed by name, [None] until the array has been walked
+    entries: Option<HashMap<String, usize>>,
+    /// Length of the array when the cache was built
+    count: usize,
+}
+
+impl LookupCache {
+    const fn new() -> Self {
+        Self {
+            entries: None,
+            count: 0,
+        }
+    }
+
+    /// Gets the address for the key, the cache is built on first use and
+    /// only rebuilt on a miss when the length of the array has changed
+    fn get(
+        &mut self,
+        key: &str,
+        count: usize,
+        build: impl FnOnce() -> HashMap<String, usize>,
+    ) -> Option<usize> {
+        let cached = self
+            .entries
+            .as_ref()
+            .and_then(|entries| entries.get(key).copied());
+
+        if cached.is_some() || (self.entries.is_some() && self.count == count) {
+            return cached;
+        }
+
+        let entries = self.entries.insert(build());
+        self.count = count;
+        entries.get(key).copied()
+    }
+}
+
+/// Function objects keyed by their full name
+static FUNCTIONS: Mutex<LookupCache> = Mutex::new(LookupCache::new());
+
+/// Name entries keyed by their lowercase name
+static NAMES: Mutex<LookupCache> = Mutex::new(LookupCache::new());
+
+/// Finds the function object with the provided full name
+/// (e.g. "Function SFXGame.SFXOnlineComponentUI.OnDisplayNotification").
+///
+/// The game objects are walked once to build a cache of the functions,
+/// the cache is only rebuilt on a miss when more objects have been loaded
+pub fn find_function(name: &str) -> Result<*mut UFunction, SdkError> {
+    let game_objects = try_game_objects_ref().ok_or(SdkError::MissingGameObjects)?;
+
+    FUNCTIONS
+        .lock()
+        .get(name, game_objects.len(), || {
+            objects()
+                .filter(|object| {
+                    unsafe { object.class.as_ref() }
+                        .is_some_and(|class| class.as_object_ref().name.matches("Function"))
+                })
+                .map(|object| (object.get_full_name(), object as *const UObject as usize))
+                .collect()
+        })
+        .map(|value| value as *mut UFunction)
+        .ok_or_else(|| SdkError::FunctionNotFound(name.to_string()))
+}
+
+/// Array type, the memory isn't freed when dropped so this type is used
+/// for arrays owned by the engine. Arrays created from Rust are owned
+/// by [OwnedTArray]
 #[repr(C)]
 #[derive(Copy)]
 pub struct TArray<T> {
@@ -40,129 +261,303 @@ pub struct TArray<T> {
     _type: PhantomData<::std::cell::UnsafeCell<T>>,
 }
 
+assert_layout!(TArray<u8>, 0x0C {
+    data: 0x00,
+    count: 0x04,
+    capacity: 0x08,
+});
+
+/// Copies the array pointer, the items aren't cloned. Use
+/// [OwnedTArray] to clone the items into a new array
 impl<T> Clone for TArray<T>
 where
-    T: Clone,
+    T: Copy,
 {
     fn clone(&self) -> Self {
-        let mut out = TArray::new();
-        for value in self.iter() {
-            out.push(value.clone());
-        }
-        out
+        *self
     }
 }
 
-pub struct TArrayIter<'a, T> {
-    arr: &'a TArray<T>,
-    index: usize,
+/// Calculates the capacity to grow to for the required number of
+/// items, matches the slack added by the engine
+fn calculate_slack(count: usize) -> usize {
+    count
+        .saturating_add(count.saturating_mul(3) / 8)
+        .saturating_add(16)
 }
 
-impl<'a, T> Iterator for TArrayIter<'a, T> {
-    type Item = &'a T;
-
-    fn next(&mut self) -> Option<Self::Item> {
-        if self.index < self.arr.len() {
-            let item = self.arr.get(self.index).expect("TArray item was null");
-            self.index += 1;
-            Some(item)
-        } else {
-            None
+impl<T> TArray<T> {
+    pub const fn new() -> Self {
+        TArray {
+            data: std::ptr::null_mut(),
+            count: 0,
+            capacity: 0,
+            _type: PhantomData,
         }
     }
-}
 
-impl<T> TArray<T> {
     /// Gets a pointer to specific element by index
     pub fn get(&self, index: usize) -> Option<&T> {
-        if index >= self.len() {
-            return None;
-        }
+        self.as_slice().get(index)
+    }
 
-        // Get a pointer to the data at the provided index
-        let item = unsafe { self.data.add(index) };
-        unsafe { item.as_ref() }
+    /// Gets a mutable pointer to specific element by index
+    pub fn get_mut(&mut self, index: usize) -> Option<&mut T> {
+        self.as_mut_slice().get_mut(index)
     }
 
     pub fn len(&self) -> usize {
         self.count as usize
     }
 
+    pub fn is_empty(&self) -> bool {
+        self.count == 0
+    }
+
     pub fn capacity(&self) -> usize {
         self.capacity as usize
     }
 
+    /// Gets the items of the array as a slice
+    pub fn as_slice(&self) -> &[T] {
+        if self.data.is_null() {
+            return &[];
+        }
+
+        unsafe { std::slice::from_raw_parts(self.data, self.len()) }
+    }
+
+    /// Gets the items of the array as a mutable slice
+    pub fn as_mut_slice(&mut self) -> &mut [T] {
+        if self.data.is_null() {
+            return &mut [];
+        }
+
+        unsafe { std::slice::from_raw_parts_mut(self.data, self.len()) }
+    }
+
     pub fn clone_vec(&self) -> Vec<T>
     where
         T: Clone,
     {
-        let mut out = Vec::with_capacity(self.len());
-        for i in 0..self.len() {
-            if let Some(value) = self.get(i) {
-                out.push(value.clone())
+        self.as_slice().to_vec()
+    }
+
+    /// Reallocates the array memory to the provided capacity, memory is
+    /// allocated through the game allocator so the engine can reallocate
+    /// or free it. `owned` is true when the memory belongs to an
+    /// [OwnedTArray] rather than the engine
+    ///
+    /// Arrays owned by the engine can't be reallocated without the game
+    /// allocator, the engine would free the memory with the wrong allocator
+    ///
+    /// # Panics
+    ///
+    /// Panics if the allocation size overflows or fails
+    fn set_capacity(&mut self, capacity: usize, owned: bool) -> Result<(), SdkError> {
+        if !owned && !memory::is_game_allocator() {
+            return Err(SdkError::MissingGameAllocator);
+        }
+
+        let size = std::mem::size_of::<T>();
+        let (old_size, new_size) = match (
+            self.capacity().checked_mul(size),
+            capacity.checked_mul(size),
+        ) {
+            (Some(old_size), Some(new_size)) if new_size <= isize::MAX as usize => {
+                (old_size, new_size)
             }
+            _ => panic!("capacity overflow"),
+        };
+        let capacity = c_int::try_from(capacity).expect("capacity overflow");
+
+        let data = unsafe {
+            memory::realloc(
+                self.data.cast(),
+                old_size,
+                new_size,
+                std::mem::align_of::<T>(),
+            )
+        };
+
+        if data.is_null() && new_size != 0 {
+            panic!("Allocation failed");
         }
-        out
+
+        self.data = data.cast();
+        self.capacity = capacity;
+        Ok(())
     }
 
-    pub const fn new() -> Self {
-        TArray {
-            data: std::ptr::null_mut(),
-            count: 0,
-            capacity: 0,
-            _type: PhantomData,
+    /// Reserves capacity for at least `additional` more items, the
+    /// engine slack is added when the array has to grow. Fails if the
+    /// array has to grow without the game allocator, arrays created from
+    /// Rust should use [OwnedTArray]
+    pub fn reserve(&mut self, additional: usize) -> Result<(), SdkError> {
+        self.reserve_in(additional, false)
+    }
+
+    fn reserve_in(&mut self, additional: usize, owned: bool) -> Result<(), SdkError> {
+        let required = self
+            .len()
+            .checked_add(additional)
+            .expect("capacity overflow");
+        if required > self.capacity() {
+            self.set_capacity(calculate_slack(required), owned)?;
         }
+        Ok(())
     }
 
-    fn grow(&mut self) {
-        let new_capacity = if self.capacity == 0 {
-            1
-        } else {
-            self.capacity * 2
-        };
-        let new_data = unsafe {
-            let layout = std::alloc::Layout::array::<T>(new_capacity as usize).unwrap();
-            let new_data = std::alloc::alloc(layout) as *mut T;
-            if new_data.is_null() {
-                panic!("Allocation failed");
-            }
-            new_data
-        };
+    /// Shrinks the capacity of the array to its length, see [TArray::reserve]
+    pub fn shrink_to_fit(&mut self) -> Result<(), SdkError> {
+        self.shrink_to_fit_in(false)
+    }
+
+    fn shrink_to_fit_in(&mut self, owned: bool) -> Result<(), SdkError> {
+        if self.capacity() > self.len() {
+            self.set_capacity(self.len(), owned)?;
+        }
+        Ok(())
+    }
+
+    /// Appends an item to the array, see [TArray::reserve]
+    pub fn push(&mut self, value: T) -> Result<(), SdkError> {
+        self.push_in(value, false)
+    }
+
+    fn push_in(&mut self, value: T, owned: bool) -> Result<(), SdkError> {
+        self.reserve_in(1, owned)?;
 
-        // Copy old data to the new allocation
         unsafe {
-            if !self.data.is_null() {
-                std::ptr::copy_nonoverlapping(self.data, new_data, self.count as usize);
-                std::alloc::dealloc(
-                    self.data as *mut u8,
-                    std::alloc::Layout::array::<T>(self.capacity as usize).unwrap(),
-                );
-            }
-            self.data = new_data;
+            let ptr = self.data.add(self.len());
+            ptr.write(value);
         }
 
-        self.capacity = new_capacity;
+        self.count += 1;
+        Ok(())
     }
 
-    pub fn push(&mut self, value: T) {
-        if self.count == self.capacity {
-            self.grow();
+    /// Removes the last item from the array
+    pub fn pop(&mut self) -> Option<T> {
+        if self.is_empty() {
+            return None;
         }
 
+        self.count -= 1;
+        Some(unsafe { self.data.add(self.len()).read() })
+    }
+
+    /// Inserts an item at the index shifting all the items after it
+    ///
+    /// Fails if the array has to grow without the game allocator, see
+    /// [TArray::reserve]
+    ///
+    /// # Panics
+    ///
+    /// Panics if the index is greater than the length
+    pub fn insert(&mut self, index: usize, value: T) -> Result<(), SdkError> {
+        self.insert_in(index, value, false)
+    }
+
+    fn insert_in(&mut self, index: usize, value: T, owned: bool) -> Result<(), SdkError> {
+        let len = self.len();
+        assert!(index <= len, "insert index {index} out of bounds ({len})");
+
+        self.reserve_in(1, owned)?;
+
         unsafe {
-            let ptr = self.data.add(self.count as usize);
+            let ptr = self.data.add(index);
+            std::ptr::copy(ptr, ptr.add(1), len - index);
             ptr.write(value);
         }
 
         self.count += 1;
+        Ok(())
     }
 
-    pub fn iter(&self) -> TArrayIter<'_, T> {
-        TArrayIter {
-            arr: self,
-            index: 0,
+    /// Removes the item at the index shifting all the items after it
+    ///
+    /// # Panics
+    ///
+    /// Panics if the index is out of bounds
+    pub fn remove(&mut self, index: usize) -> T {
+        let len = self.len();
+        assert!(index < len, "remove index {index} out of bounds ({len})");
+
+        unsafe {
+            let ptr = self.data.add(index);
+            let value = ptr.read();
+            std::ptr::copy(ptr.add(1), ptr, len - index - 1);
+            self.count -= 1;
+            value
         }
     }
+
+    /// Removes all the items from the array keeping the allocated memory
+    pub fn clear(&mut self) {
+        let items: *mut [T] = self.as_mut_slice();
+
+        // Length is reset first in case dropping an item panics
+        self.count = 0;
+
+        unsafe { std::ptr::drop_in_place(items) }
+    }
+
+    pub fn iter(&self) -> std::slice::Iter<'_, T> {
+        self.as_slice().iter()
+    }
+
+    pub fn iter_mut(&mut self) -> std::slice::IterMut<'_, T> {
+        self.as_mut_slice().iter_mut()
+    }
+
+    /// Drops the items and frees the array memory
+    ///
+    /// # Safety
+    ///
+    /// The memory must be owned by Rust and not referenced by anything else
+    unsafe fn free(&mut self) {
+        self.clear();
+        _ = self.set_capacity(0, true);
+    }
+}
+
+impl<T> Default for TArray<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T> Index<usize> for TArray<T> {
+    type Output = T;
+
+    fn index(&self, index: usize) -> &Self::Output {
+        &self.as_slice()[index]
+    }
+}
+
+impl<T> IndexMut<usize> for TArray<T> {
+    fn index_mut(&mut self, index: usize) -> &mut Self::Output {
+        &mut self.as_mut_slice()[index]
+    }
+}
+
+impl<'a, T> IntoIterator for &'a TArray<T> {
+    type Item = &'a T;
+    type IntoIter = std::slice::Iter<'a, T>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+impl<'a, T> IntoIterator for &'a mut TArray<T> {
+    type Item = &'a mut T;
+    type IntoIter = std::slice::IterMut<'a, T>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter_mut()
+    }
 }
 
 impl<T> Debug for TArray<T>
@@ -174,86 +569,304 @@ where
     }
 }
 
-impl<T> From<Vec<T>> for TArray<T> {
-    fn from(value: Vec<T>) -> Self {
-        let length = value.len() as c_int;
-        let capacity = value.capacity() as c_int;
-        let value = value.leak();
+/// Array allocated through the game allocator that is owned by Rust,
+/// the items and memory are freed when dropped. Copies of the underlying
+/// [TArray] must not outlive the owner
+#[repr(transparent)]
+pub struct OwnedTArray<T>(TArray<T>);
+
+impl<T> OwnedTArray<T> {
+    pub const fn new() -> Self {
+        Self(TArray::new())
+    }
 
-        let data = value.as_mut_ptr();
+    /// Takes the underlying array without freeing it, used when ownership
+    /// of the memory is moved to the engine. Fails when the array memory
+    /// wasn't allocated through the game allocator, the array is dropped
+    pub fn into_raw(self) -> Result<TArray<T>, SdkError> {
+        if self.capacity() > 0 && !memory::is_game_allocator() {
+            return Err(SdkError::MissingGameAllocator);
+        }
+        Ok(self.leak())
+    }
 
-        Self {
-            data,
-            count: length,
-            capacity,
-            _type: PhantomData,
+    /// Takes the underlying array without freeing it
+    fn leak(self) -> TArray<T> {
+        let this = ManuallyDrop::new(self);
+        unsafe { std::ptr::read(&this.0) }
+    }
+
+    /// Reserves capacity for at least `additional` more items, the memory
+    /// is allocated with the Rust allocator when the game allocator isn't
+    /// available
+    pub fn reserve(&mut self, additional: usize) {
+        // Owned arrays can always be reallocated
+        _ = self.0.reserve_in(additional, true);
+    }
+
+    /// Shrinks the capacity of the array to its length
+    pub fn shrink_to_fit(&mut self) {
+        _ = self.0.shrink_to_fit_in(true);
+    }
+
+    pub fn push(&mut self, value: T) {
+        _ = self.0.push_in(value, true);
+    }
+
+    /// Inserts an item at the index shifting all the items after it
+    ///
+    /// # Panics
+    ///
+    /// Panics if the index is greater than the length
+    pub fn insert(&mut self, index: usize, value: T) {
+        _ = self.0.insert_in(index, value, true);
+    }
+}
+
+impl<T> Default for OwnedTArray<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T> Deref for OwnedTArray<T> {
+    type Target = TArray<T>;
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+impl<T> DerefMut for OwnedTArray<T> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.0
+    }
+}
+
+impl<T> Drop for OwnedTArray<T> {
+    fn drop(&mut self) {
+        unsafe { self.0.free() }
+    }
+}
+
+impl<T> Clone for OwnedTArray<T>
+where
+    T: Clone,
+{
+    fn clone(&self) -> Self {
+        let mut out = OwnedTArray::new();
+        out.reserve(self.len());
+        for value in self.iter() {
+            out.push(value.clone());
+        }
+        out
+    }
+}
+
+impl<T> From<Vec<T>> for OwnedTArray<T> {
+    fn from(value: Vec<T>) -> Self {
+        let mut out = OwnedTArray::new();
+        out.reserve(value.len());
+        for value in value {
+            out.push(value);
         }
+        out
+    }
+}
+
+impl<T> Debug for OwnedTArray<T>
+where
+    T: Debug,
+{
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        Debug::fmt(&self.0, f)
     }
 }
 
+/// Engine string, the memory isn't freed when dropped so this type is
+/// used to borrow strings owned by the engine. Strings created from Rust
+/// are owned by [OwnedFString]
 #[repr(C)]
-#[derive(Clone, Copy)]
+#[derive(Clone, Copy, Default)]
 pub struct FString(TArray<i16>);
 
-impl Default for FString {
-    fn default() -> Self {
-        Self(TArray::from(vec![0]))
+assert_layout!(FString, 0x0C { 0: 0x00 });
+
+impl FString {
+    /// Gets the UTF-16 characters of the string up to the null terminator
+    pub fn as_utf16(&self) -> &[u16] {
+        let value = self.0.as_slice();
+        let length = value
+            .iter()
+            .position(|value| *value == 0)
+            .unwrap_or(value.len());
+
+        // Safety: i16 and u16 have the same layout
+        unsafe { std::slice::from_raw_parts(value.as_ptr().cast::<u16>(), length) }
+    }
+
+    /// Number of UTF-16 characters in the string excluding the null terminator
+    pub fn len(&self) -> usize {
+        self.as_utf16().len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.as_utf16().is_empty()
+    }
+
+    /// Converts the string replacing any invalid characters (Such as
+    /// unpaired surrogates) with the replacement character
+    pub fn to_string_lossy(&self) -> String {
+        String::from_utf16_lossy(self.as_utf16())
     }
 }
 
-impl FString {
-    pub fn from_string(mut value: String) -> FString {
-        // String must be null terminated
-        if !value.ends_with('\0') {
-            value.push('\0')
-        }
+impl TryFrom<&FString> for String {
+    type Error = FromUtf16Error;
 
-        let value = value
-            .encode_utf16()
-            .map(|value| value as i16)
-            .collect::<Vec<_>>();
-        FString(TArray::from(value))
+    fn try_from(value: &FString) -> Result<Self, Self::Error> {
+        String::from_utf16(value.as_utf16())
     }
+}
 
-    pub fn from_str_with_null(value: &str) -> FString {
-        // String must be null terminated
-        if !value.ends_with('\0') {
-            panic!("FString::from_str missing null terminator \"{value}\"");
-        }
+impl PartialEq<str> for FString {
+    fn eq(&self, other: &str) -> bool {
+        self.as_utf16().iter().copied().eq(other.encode_utf16())
+    }
+}
 
-        let value = value
-            .encode_utf16()
-            .map(|value| value as i16)
-            .collect::<Vec<_>>();
-        FString(TArray::from(value))
+impl PartialEq<&str> for FString {
+    fn eq(&self, other: &&str) -> bool {
+        self.eq(*other)
     }
 }
 
 impl Debug for FString {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        Display::fmt(&self, f)
+        Debug::fmt(&self.to_string_lossy(), f)
     }
 }
 
 impl Display for FString {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        let out = decode_utf16(self.0.iter().map(|value| *value as u16))
-            .try_fold(String::new(), |mut accu, value| {
-                if let Ok(value) = value {
-                    accu.push(value);
-                    Some(accu)
-                } else {
-                    None
-                }
-            })
-            .unwrap();
-        f.write_str(&out)
+        for value in decode_utf16(self.as_utf16().iter().copied()) {
+            f.write_char(value.unwrap_or(REPLACEMENT_CHARACTER))?;
+        }
+        Ok(())
+    }
+}
+
+/// String allocated through the game allocator that is owned by Rust and
+/// freed when dropped. Copies of the underlying [FString] (e.g. in a params
+/// block) must not outlive the owner
+#[repr(transparent)]
+pub struct OwnedFString(FString);
+
+impl OwnedFString {
+    /// Creates a null terminated string from the value, empty strings
+    /// have no allocation like the engine
+    pub fn new(value: &str) -> Self {
+        let value = value.strip_suffix('\0').unwrap_or(value);
+        let mut out = OwnedTArray::new();
+
+        if !value.is_empty() {
+            out.reserve(value.len() + 1);
+            for value in value.encode_utf16() {
+                out.push(value as i16);
+            }
+
+            // String must be null terminated
+            out.push(0);
+        }
+
+        Self(FString(out.leak()))
+    }
+
+    /// Takes the underlying string without freeing it, used when ownership
+    /// of the memory is moved to the engine. Fails when the string wasn't
+    /// allocated through the game allocator, the string is dropped
+    pub fn into_raw(self) -> Result<FString, SdkError> {
+        if self.0 .0.capacity() > 0 && !memory::is_game_allocator() {
+            return Err(SdkError::MissingGameAllocator);
+        }
+
+        let value = self.0;
+        std::mem::forget(self);
+        Ok(value)
+    }
+}
+
+impl From<&str> for OwnedFString {
+    fn from(value: &str) -> Self {
+        Self::new(value)
+    }
+}
+
+impl Deref for OwnedFString {
+    type Target = FString;
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+impl Drop for OwnedFString {
+    fn drop(&mut self) {
+        unsafe { self.0 .0.free() }
+    }
+}
+
+impl PartialEq<str> for OwnedFString {
+    fn eq(&self, other: &str) -> bool {
+        self.0.eq(other)
+    }
+}
+
+impl PartialEq<&str> for OwnedFString {
+    fn eq(&self, other: &&str) -> bool {
+        self.0.eq(*other)
+    }
+}
+
+impl Debug for OwnedFString {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        Debug::fmt(&self.0, f)
+    }
+}
+
+impl Display for OwnedFString {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        Display::fmt(&self.0, f)
     }
 }
 
 #[repr(C)]
 pub struct UObjectVTable(c_void);
 
+/// Maximum number of outer objects walked, guards against cycles
+/// from objects that are being destroyed
+const MAX_OUTER_DEPTH: usize = 64;
+
+/// Full names of objects keyed by the object address
+static FULL_NAMES: Mutex<Option<HashMap<usize, CachedFullName>>> = Mutex::new(None);
+
+/// Cached full name along with the parts of the object it was built from,
+/// used to detect when the object or any of its outers has been replaced
+/// by another object at the same address
+struct CachedFullName {
+    parts: Vec<(usize, FName)>,
+    full_name: Arc<str>,
+}
+
+// Pointers are only compared and never dereferenced
+unsafe impl Send for CachedFullName {}
+
+impl CachedFullName {
+    fn is_same(&self, object: &UObject) -> bool {
+        self.parts.iter().copied().eq(object.full_name_parts())
+    }
+}
+
 #[derive(Debug, Clone, Copy)]
 #[repr(C, packed(4))]
 pub struct UObject {
@@ -272,39 +885,127 @@ pub struct UObject {
     pub object_archetype: *mut UObject,
 }
 
+assert_layout!(UObject, 0x3C {
+    vtable_: 0x00,
+    object_internal_integer: 0x04,
+    object_flags: 0x08,
+    hash_next: 0x10,
+    hash_outer_next: 0x14,
+    state_frame: 0x18,
+    linker: 0x1C,
+    linker_index: 0x20,
+    net_index: 0x24,
+    outer: 0x28,
+    name: 0x2C,
+    class: 0x34,
+    object_archetype: 0x38,
+});
+
 impl UObject {
     pub fn cast<T>(&self) -> *const T {
         self as *const UObject as *const T
     }
 
-    /// Collects the full name of the object
+    /// Collects the full name of the object in the same format as the
+    /// engine (e.g. "Function SFXGame.SFXOnlineComponentUI.OnDisplayNotification")
     pub fn get_full_name(&self) -> String {
-        match unsafe { (self.class.as_ref(), self.outer.as_ref()) } {
-            (Some(class), Some(outer)) => {
-                let class_name = class.get_name().to_str().expect("Class name invalid utf8");
-                let outer_name = outer.get_name().to_str().expect("Class name invalid utf8");
-                let this_name = self.get_name().to_str().expect("Class name invalid utf8");
-
-                if let Some(outer) = unsafe { outer.outer.as_ref() } {
-                    let outer_outer_name =
-                        outer.get_name().to_str().expect("Class name invalid utf8");
-
-                    format!(
-                        "{} {}.{}.{}",
-                        class_name, outer_outer_name, outer_name, this_name
-                    )
-                } else {
-                    format!("{} {}.{}", class_name, outer_name, this_name)
-                }
+        let class = match unsafe { self.class.as_ref() } {
+            Some(value) => value,
+            None => return "(null)".to_string(),
+        };
+
+        let mut out = class.get_name();
+        out.push(' ');
+        self.write_path_name(&mut out);
+        out
+    }
+
+    /// Gets the full name of the object, names are cached by the object
+    /// address and only collected again when the object, its class or any
+    /// of its outers has changed
+    pub fn get_full_name_cached(&self) -> Arc<str> {
+        let mut cache = FULL_NAMES.lock();
+        let cache = cache.get_or_insert_with(HashMap::new);
+
+        match cache.get(&(self as *const UObject as usize)) {
+            Some(cached) if cached.is_same(self) => cached.full_name.clone(),
+            _ => {
+                let full_name: Arc<str> = Arc::from(self.get_full_name());
+                cache.insert(
+                    self as *const UObject as usize,
+                    CachedFullName {
+                        parts: self.full_name_parts().collect(),
+                        full_name: full_name.clone(),
+                    },
+                );
+                full_name
             }
-            _ => "(null)".to_string(),
         }
     }
 
-    pub fn get_name(&self) -> &CStr {
+    /// Address and name of the class, the object and each of its outers,
+    /// the parts the full name is built from
+    fn full_name_parts(&self) -> impl Iterator<Item = (usize, FName)> + '_ {
+        let class = unsafe { self.class.as_ref() }
+            .map(|class| (class as *const UClass as usize, class.as_object_ref().name));
+        let objects = std::iter::successors(Some(self), |value| unsafe { value.outer.as_ref() })
+            .take(MAX_OUTER_DEPTH + 1)
+            .map(|value| (value as *const UObject as usize, value.name));
+
+        class.into_iter().chain(objects)
+    }
+
+    /// Collects the path name of the object, the names of each of the
+    /// outer objects separated by "." (e.g. "SFXGame.SFXOnlineComponentUI")
+    pub fn get_path_name(&self) -> String {
+        let mut out = String::new();
+        self.write_path_name(&mut out);
+        out
+    }
+
+    fn write_path_name(&self, out: &mut String) {
+        let outers: Vec<&UObject> =
+            std::iter::successors(unsafe { self.outer.as_ref() }, |value| unsafe {
+                value.outer.as_ref()
+            })
+            .take(MAX_OUTER_DEPTH)
+            .collect();
+
+        for outer in outers.into_iter().rev() {
+            _ = write!(out, "{}.", outer.name);
+        }
+
+        _ = write!(out, "{}", self.name);
+    }
+
+    /// Gets the name of the object including any numeric suffix
+    pub fn get_name(&self) -> String {
         self.name.get_name()
     }
 
+    /// Checks if the object is an instance of the class or one of its sub classes
+    pub fn is_a(&self, class: &UClass) -> bool {
+        unsafe { self.class.as_ref() }
+            .is_some_and(|value| value.hierarchy().any(|value| std::ptr::eq(value, class)))
+    }
+
+    /// Checks if the object is an instance of the class with the provided
+    /// name or one of its sub classes
+    pub fn is_a_named(&self, class_name: &str) -> bool {
+        unsafe { self.class.as_ref() }.is_some_and(|value| {
+            value
+                .hierarchy()
+                .any(|value| value.as_object_ref().name.matches(class_name))
+        })
+    }
+
+    /// Checks if the object is the default object for its class
+    pub fn is_default_object(&self) -> bool {
+        self.name
+            .entry()
+            .is_some_and(|entry| entry.get_name().starts_with("Default__"))
+    }
+
     pub fn process_event(
         &self,
         function: *mut UFunction,
@@ -336,47 +1037,195 @@ pub struct FQWord {
     pub b: c_int,
 }
 
+assert_layout!(FQWord, 0x08 { a: 0x00, b: 0x04 });
+
 #[derive(Debug, Clone, Copy)]
 #[repr(C)]
 pub struct FPointer {
     pub dummy: c_int,
 }
 
-#[derive(Debug, Clone, Copy)]
+assert_layout!(FPointer, 0x04 { dummy: 0x00 });
+
+/// Name made up of an entry from the name table and an instance number
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 #[repr(C)]
 pub struct FName {
     pub name_entry: *mut FNameEntry,
+    /// Instance number of the name, zero when the name has no numeric
+    /// suffix otherwise the suffix plus one (e.g. 4 for "Name_3")
     pub name_index: c_uint,
 }
 
+assert_layout!(FName, 0x08 {
+    name_entry: 0x00,
+    name_index: 0x04,
+});
+
+/// Splits the numeric suffix from a name returning the base name and the
+/// instance number, suffixes with leading zeros aren't split
+fn split_number(value: &str) -> Option<(&str, c_uint)> {
+    let (base, suffix) = value.rsplit_once('_')?;
+
+    if base.is_empty()
+        || suffix.is_empty()
+        || !suffix.bytes().all(|value| value.is_ascii_digit())
+        || (suffix.len() > 1 && suffix.starts_with('0'))
+    {
+        return None;
+    }
+
+    let number = suffix.parse::<c_uint>().ok()?.checked_add(1)?;
+    Some((base, number))
+}
+
 impl FName {
-    /// Gets the name from the entry, name is stored
-    /// in the name char
-    pub fn get_name(&self) -> &CStr {
-        unsafe {
-            self.name_entry
-                .as_ref()
-                .expect("Name entry pointer was null")
-                .get_name()
+    /// Finds the name in the name table, a numeric suffix (e.g. "_3")
+    /// is stored as the instance number. Names are case insensitive
+    pub fn find(value: &str) -> Result<FName, SdkError> {
+        let game_names = try_game_names_ref().ok_or(SdkError::MissingGameNames)?;
+        let mut cache = NAMES.lock();
+
+        let mut lookup = |name: &str| {
+            cache.get(&name.to_ascii_lowercase(), game_names.len(), || {
+                game_names
+                    .iter()
+                    .filter_map(|entry| unsafe { entry.as_ref() })
+                    .map(|entry| {
+                        let address = entry as *const FNameEntry as usize;
+                        (entry.get_name().to_ascii_lowercase(), address)
+                    })
+                    .collect()
+            })
+        };
+
+        // Names with a suffix may also exist as a whole
+        let found = match split_number(value) {
+            Some((base, number)) => lookup(base).map(|entry| (entry, number)),
+            None => None,
         }
+        .or_else(|| lookup(value).map(|entry| (entry, 0)));
+
+        let (entry, number) = found.ok_or_else(|| SdkError::NameNotFound(value.to_string()))?;
+
+        Ok(FName {
+            name_entry: entry as *mut FNameEntry,
+            name_index: number,
+        })
+    }
+
+    /// Gets the name table entry for this name
+    pub fn entry(&self) -> Option<&FNameEntry> {
+        unsafe { self.name_entry.as_ref() }
+    }
+
+    /// Gets the name including the numeric suffix
+    pub fn get_name(&self) -> String {
+        self.to_string()
+    }
+
+    /// Checks if the name matches the provided value, names are case
+    /// insensitive and the value must include the numeric suffix
+    pub fn matches(&self, value: &str) -> bool {
+        let entry = match self.entry() {
+            Some(value) => value,
+            None => return false,
+        };
+
+        if self.name_index == 0 {
+            return entry.get_name().eq_ignore_ascii_case(value);
+        }
+
+        split_number(value).is_some_and(|(base, number)| {
+            number == self.name_index && entry.get_name().eq_ignore_ascii_case(base)
+        })
     }
 }
 
-// Name entry
-#[derive(Debug, Clone, Copy)]
+impl Display for FName {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let entry = match self.entry() {
+            Some(value) => value,
+            None => return f.write_str("(null)"),
+        };
+
+        f.write_str(&entry.get_name())?;
+
+        if self.name_index > 0 {
+            write!(f, "_{}", self.name_index - 1)?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Entry within the name table, entries are only allocated large enough
+/// for their name which is stored as either ANSI or wide characters
+/// directly after the header fields
 #[repr(C)]
 pub struct FNameEntry {
-    // Unknown block of data
-    pub unknown_data00: [c_uchar; 8usize],
-    // Name array data
-    pub name: [c_char; 16usize],
+    /// Index of the entry within the name table shifted left by one,
+    /// the low bit is set when the name is stored as wide characters
+    pub index: c_int,
+    /// Next entry within the same hash bucket
+    pub hash_next: *mut FNameEntry,
 }
 
+assert_layout!(FNameEntry, 0x08 {
+    index: 0x00,
+    hash_next: 0x04,
+});
+
+/// Offset of the null terminated name characters within an entry
+const NAME_OFFSET: usize = 0x08;
+
 impl FNameEntry {
-    /// Gets the name from the entry, name is stored
-    /// in the name char
-    pub fn get_name(&self) -> &CStr {
-        unsafe { CStr::from_ptr(self.name.as_ptr()) }
+    /// Whether the name is stored as wide characters
+    pub fn is_wide(&self) -> bool {
+        self.index & 1 != 0
+    }
+
+    /// Index of the entry within the name table
+    pub fn table_index(&self) -> usize {
+        (self.index >> 1) as usize
+    }
+
+    /// Gets the name from the entry, ANSI names are borrowed when they
+    /// only contain ASCII characters
+    pub fn get_name(&self) -> Cow<'_, str> {
+        // The name lies past the end of the struct, so the pointer is derived
+        // from a raw pointer to the entry rather than from a field reference
+        let start = unsafe {
+            (self as *const Self)
+                .cast::<u8>()
+                .add(NAME_OFFSET)
+                .cast::<c_char>()
+        };
+
+        if self.is_wide() {
+            let start = start.cast::<u16>();
+            let value = unsafe {
+                let length = (0..).take_while(|offset| *start.add(*offset) != 0).count();
+                std::slice::from_raw_parts(start, length)
+            };
+            return Cow::Owned(String::from_utf16_lossy(value));
+        }
+
+        let value = unsafe { CStr::from_ptr(start) }.to_bytes();
+        match std::str::from_utf8(value) {
+            Ok(value) if value.is_ascii() => Cow::Borrowed(value),
+            // Non ASCII characters are Latin-1
+            _ => Cow::Owned(value.iter().map(|value| *value as char).collect()),
+        }
+    }
+}
+
+impl Debug for FNameEntry {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("FNameEntry")
+            .field("index", &self.table_index())
+            .field("name", &self.get_name())
+            .finish()
     }
 }
 
@@ -387,11 +1236,27 @@ pub struct UClass {
     pub unknown_data00: [c_uchar; 188usize],
 }
 
+assert_layout!(UClass, 0x164 {
+    _base: 0x00,
+    unknown_data00: 0xA8,
+});
+
 impl UClass {
-    pub fn get_name(&self) -> &CStr {
+    pub fn get_name(&self) -> String {
         self._base.get_name()
     }
 
+    /// Gets the class this class extends
+    pub fn super_class(&self) -> Option<&UClass> {
+        let super_field = self._base._base._base.super_field;
+        unsafe { super_field.cast::<UClass>().as_ref() }
+    }
+
+    /// Iterates this class followed by each of its super classes
+    pub fn hierarchy(&self) -> impl Iterator<Item = &UClass> {
+        std::iter::successors(Some(self), |value| value.super_class())
+    }
+
     pub fn as_object_ref(&self) -> &UObject {
         self._base.as_object_ref()
     }
@@ -404,8 +1269,13 @@ pub struct UState {
     pub unknown_data00: [c_uchar; 36usize],
 }
 
+assert_layout!(UState, 0xA8 {
+    _base: 0x00,
+    unknown_data00: 0x84,
+});
+
 impl UState {
-    pub fn get_name(&self) -> &CStr {
+    pub fn get_name(&self) -> String {
         self._base.get_name()
     }
 
@@ -421,8 +1291,13 @@ pub struct UStruct {
     pub unknown_data00: [c_uchar; 64usize],
 }
 
+assert_layout!(UStruct, 0x84 {
+    _base: 0x00,
+    unknown_data00: 0x44,
+});
+
 impl UStruct {
-    pub fn get_name(&self) -> &CStr {
+    pub fn get_name(&self) -> String {
         self._base.get_name()
     }
 
@@ -439,8 +1314,14 @@ pub struct UField {
     pub next: *mut UField,
 }
 
+assert_layout!(UField, 0x44 {
+    _base: 0x00,
+    super_field: 0x3C,
+    next: 0x40,
+});
+
 impl UField {
-    pub fn get_name(&self) -> &CStr {
+    pub fn get_name(&self) -> String {
         self._base.get_name()
     }
 
@@ -459,8 +1340,16 @@ pub struct UFunction {
     pub unknown_data00: [c_uchar; 8usize],
 }
 
+assert_layout!(UFunction, 0x98 {
+    _base: 0x00,
+    func: 0x84,
+    function_flags: 0x88,
+    i_native: 0x8C,
+    unknown_data00: 0x8E,
+});
+
 impl UFunction {
-    pub fn get_name(&self) -> &CStr {
+    pub fn get_name(&self) -> String {
         self._base.get_name()
     }
 
@@ -472,5 +1361,501 @@ impl UFunction {
 #[derive(Debug, Clone, Copy)]
 #[repr(C)]
 pub struct FScriptDelegate {
-    pub UnknownData00: [::std::os::raw::c_uchar; 12usize],
+    pub unknown_data00: [::std::os::raw::c_uchar; 12usize],
+}
+
+assert_layout!(FScriptDelegate, 0x0C { unknown_data00: 0x00 });
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::{ptr::null_mut, rc::Rc};
+
+    #[test]
+    fn slack() {
+        assert_eq!(calculate_slack(0), 16);
+        assert_eq!(calculate_slack(1), 17);
+        assert_eq!(calculate_slack(8), 27);
+        assert_eq!(calculate_slack(17), 39);
+        assert_eq!(calculate_slack(100), 153);
+        assert_eq!(calculate_slack(usize::MAX), usize::MAX);
+    }
+
+    #[test]
+    fn push_pop() {
+        let mut array = OwnedTArray::new();
+        assert!(array.is_empty());
+        assert_eq!(array.capacity(), 0);
+        assert_eq!(array.pop(), None);
+
+        array.push(1);
+        assert_eq!(array.capacity(), calculate_slack(1));
+        for value in 2..=20 {
+            array.push(value);
+        }
+
+        // Grows with slack once the first allocation is full
+        assert_eq!(array.len(), 20);
+        assert_eq!(array.capacity(), calculate_slack(18));
+        assert_eq!(array.as_slice(), (1..=20).collect::<Vec<_>>());
+
+        assert_eq!(array.pop(), Some(20));
+        assert_eq!(array.pop(), Some(19));
+        assert_eq!(array.len(), 18);
+        assert_eq!(array[17], 18);
+    }
+
+    #[test]
+    fn insert_remove() {
+        let mut array = OwnedTArray::from(vec![1, 2, 3]);
+
+        array.insert(0, 0);
+        array.insert(2, 10);
+        array.insert(5, 4);
+        assert_eq!(array.as_slice(), [0, 1, 10, 2, 3, 4]);
+
+        assert_eq!(array.remove(2), 10);
+        assert_eq!(array.remove(0), 0);
+        assert_eq!(array.remove(3), 4);
+        assert_eq!(array.as_slice(), [1, 2, 3]);
+    }
+
+    #[test]
+    #[should_panic(expected = "insert index 4 out of bounds (3)")]
+    fn insert_out_of_bounds() {
+        let mut array = OwnedTArray::from(vec![1, 2, 3]);
+        array.insert(4, 0);
+    }
+
+    #[test]
+    #[should_panic(expected = "remove index 3 out of bounds (3)")]
+    fn remove_out_of_bounds() {
+        let mut array = OwnedTArray::from(vec![1, 2, 3]);
+        array.remove(3);
+    }
+
+    #[test]
+    fn clear_shrink() {
+        let mut array = OwnedTArray::from(vec![1, 2, 3]);
+        assert_eq!(array.capacity(), calculate_slack(3));
+
+        array.shrink_to_fit();
+        assert_eq!(array.capacity(), 3);
+        assert_eq!(array.as_slice(), [1, 2, 3]);
+
+        array.push(4);
+        assert_eq!(array.capacity(), calculate_slack(4));
+
+        array.clear();
+        assert!(array.is_empty());
+        assert_eq!(array.capacity(), calculate_slack(4));
+
+        array.shrink_to_fit();
+        assert_eq!(array.capacity(), 0);
+        assert!(array.as_slice().is_empty());
+    }
+
+    #[test]
+    fn index() {
+        let mut array = OwnedTArray::from(vec![1, 2, 3]);
+        array[1] = 5;
+        assert_eq!(array[1], 5);
+        assert_eq!(array.get(3), None);
+    }
+
+    #[test]
+    #[should_panic]
+    fn index_out_of_bounds() {
+        let array = OwnedTArray::from(vec![1, 2, 3]);
+        _ = array[3];
+    }
+
+    #[test]
+    #[should_panic]
+    fn index_empty() {
+        let array: TArray<i32> = TArray::new();
+        _ = array[0];
+    }
+
+    #[test]
+    fn owned_drops_items() {
+        let value = Rc::new(());
+
+        let array = OwnedTArray::from(vec![value.clone(), value.clone()]);
+        let cloned = array.clone();
+        assert_eq!(Rc::strong_count(&value), 5);
+
+        drop(array);
+        assert_eq!(Rc::strong_count(&value), 3);
+
+        let mut cloned = cloned;
+        cloned.clear();
+        assert_eq!(Rc::strong_count(&value), 1);
+
+        cloned.push(value.clone());
+        drop(cloned);
+        assert_eq!(Rc::strong_count(&value), 1);
+    }
+
+    #[test]
+    fn owned_leak() {
+        let value = Rc::new(());
+
+        let mut raw = OwnedTArray::from(vec![value.clone()]).leak();
+        assert_eq!(Rc::strong_count(&value), 2);
+
+        // Ownership is taken back to free the memory
+        unsafe { raw.free() };
+        assert_eq!(Rc::strong_count(&value), 1);
+    }
+
+    #[test]
+    fn engine_array_without_allocator() {
+        let mut array: TArray<i32> = TArray::new();
+        assert_eq!(array.push(1), Err(SdkError::MissingGameAllocator));
+        assert_eq!(array.reserve(4), Err(SdkError::MissingGameAllocator));
+        assert_eq!(array.insert(0, 1), Err(SdkError::MissingGameAllocator));
+        assert!(array.is_empty());
+
+        // Nothing to reallocate
+        assert_eq!(array.reserve(0), Ok(()));
+        assert_eq!(array.shrink_to_fit(), Ok(()));
+    }
+
+    #[test]
+    fn into_raw_without_allocator() {
+        let value = Rc::new(());
+
+        // Memory allocated with the Rust allocator can't be given to the engine
+        let array = OwnedTArray::from(vec![value.clone()]);
+        assert_eq!(array.into_raw().err(), Some(SdkError::MissingGameAllocator));
+        assert_eq!(Rc::strong_count(&value), 1);
+
+        let string = OwnedFString::new("value");
+        assert_eq!(
+            string.into_raw().err(),
+            Some(SdkError::MissingGameAllocator)
+        );
+
+        // Empty values have no allocation
+        assert!(OwnedTArray::<i32>::new().into_raw().is_ok());
+        assert!(OwnedFString::new("").into_raw().is_ok());
+    }
+
+    #[test]
+    #[should_panic(expected = "capacity overflow")]
+    fn capacity_overflow() {
+        let mut array: OwnedTArray<u64> = OwnedTArray::new();
+        array.reserve(usize::MAX / 4);
+    }
+
+    /// Builds a name table entry with the name bytes following the header,
+    /// backed by u64s so the entry is aligned
+    fn name_entry(index: c_int, wide: bool, name: &str) -> Vec<u64> {
+        let mut bytes = Vec::new();
+        bytes.extend_from_slice(&((index << 1) | wide as c_int).to_le_bytes());
+        bytes.resize(NAME_OFFSET, 0);
+
+        if wide {
+            name.encode_utf16()
+                .chain([0])
+                .for_each(|value| bytes.extend_from_slice(&value.to_le_bytes()));
+        } else {
+            // Latin-1 characters are stored as single bytes
+            bytes.extend(name.chars().map(|value| value as u8));
+            bytes.push(0);
+        }
+
+        bytes
+            .chunks(8)
+            .map(|chunk| {
+                let mut value = [0; 8];
+                value[..chunk.len()].copy_from_slice(chunk);
+                u64::from_le_bytes(value)
+            })
+            .collect()
+    }
+
+    fn entry_ref(entry: &[u64]) -> &FNameEntry {
+        unsafe { &*entry.as_ptr().cast::<FNameEntry>() }
+    }
+
+    fn name(entry: &mut [u64], name_index: c_uint) -> FName {
+        FName {
+            name_entry: entry.as_mut_ptr().cast(),
+            name_index,
+        }
+    }
+
+    #[test]
+    fn name_entry_ansi() {
+        let entry = name_entry(5, false, "Foo");
+        let entry = entry_ref(&entry);
+        assert!(!entry.is_wide());
+        assert_eq!(entry.table_index(), 5);
+        assert!(matches!(entry.get_name(), Cow::Borrowed("Foo")));
+
+        let entry = name_entry(6, false, "Caf\u{e9}");
+        assert_eq!(entry_ref(&entry).get_name(), "Caf\u{e9}");
+
+        let entry = name_entry(0, false, "");
+        assert_eq!(entry_ref(&entry).get_name(), "");
+    }
+
+    #[test]
+    fn name_entry_wide() {
+        let entry = name_entry(7, true, "W\u{ef}de\u{20ac}");
+        let entry = entry_ref(&entry);
+        assert!(entry.is_wide());
+        assert_eq!(entry.table_index(), 7);
+        assert!(matches!(entry.get_name(), Cow::Owned(_)));
+        assert_eq!(entry.get_name(), "W\u{ef}de\u{20ac}");
+    }
+
+    #[test]
+    fn split_numbers() {
+        assert_eq!(split_number("Foo_0"), Some(("Foo", 1)));
+        assert_eq!(split_number("Foo_12"), Some(("Foo", 13)));
+        assert_eq!(split_number("Foo_Bar_3"), Some(("Foo_Bar", 4)));
+
+        assert_eq!(split_number("Foo"), None);
+        assert_eq!(split_number("Foo_"), None);
+        assert_eq!(split_number("_1"), None);
+        assert_eq!(split_number("Foo_01"), None);
+        assert_eq!(split_number("Foo_1a"), None);
+        assert_eq!(split_number("Foo_4294967295"), None);
+    }
+
+    #[test]
+    fn name_display() {
+        let mut entry = name_entry(1, false, "Foo");
+        assert_eq!(name(&mut entry, 0).to_string(), "Foo");
+        assert_eq!(name(&mut entry, 1).to_string(), "Foo_0");
+        assert_eq!(name(&mut entry, 13).get_name(), "Foo_12");
+
+        // Suffixes round trip through the instance number
+        for value in ["Foo_0", "Foo_12"] {
+            let (_, number) = split_number(value).unwrap();
+            assert_eq!(name(&mut entry, number).to_string(), value);
+        }
+
+        let mut entry = name_entry(2, true, "Wide");
+        assert_eq!(name(&mut entry, 4).to_string(), "Wide_3");
+
+        let null = FName {
+            name_entry: std::ptr::null_mut(),
+            name_index: 0,
+        };
+        assert_eq!(null.to_string(), "(null)");
+    }
+
+    #[test]
+    fn name_matches() {
+        let mut entry = name_entry(1, false, "Foo");
+
+        let plain = name(&mut entry, 0);
+        assert!(plain.matches("Foo"));
+        assert!(plain.matches("fOO"));
+        assert!(!plain.matches("Foo_0"));
+        assert!(!plain.matches("Bar"));
+
+        let first = name(&mut entry, 1);
+        assert!(first.matches("Foo_0"));
+        assert!(first.matches("FOO_0"));
+        assert!(!first.matches("Foo"));
+        assert!(!first.matches("Foo_1"));
+
+        let twelfth = name(&mut entry, 13);
+        assert!(twelfth.matches("Foo_12"));
+        assert!(!twelfth.matches("Foo_012"));
+        assert!(!twelfth.matches("Bar_12"));
+
+        // Names that end in a suffix themselves only match when unnumbered
+        let mut entry = name_entry(2, false, "Foo_3");
+        assert!(name(&mut entry, 0).matches("Foo_3"));
+
+        let null = FName {
+            name_entry: std::ptr::null_mut(),
+            name_index: 0,
+        };
+        assert!(!null.matches("(null)"));
+    }
+
+    /// Builds an object with the name and outer, other fields are zeroed
+    fn object(name: FName, outer: *mut UObject, class: *mut UClass) -> Box<UObject> {
+        let mut object: Box<UObject> = Box::new(unsafe { std::mem::zeroed() });
+        object.name = name;
+        object.outer = outer;
+        object.class = class;
+        object
+    }
+
+    fn class(name: FName) -> Box<UClass> {
+        let mut class: Box<UClass> = Box::new(unsafe { std::mem::zeroed() });
+        class._base._base._base._base.name = name;
+        class
+    }
+
+    #[test]
+    fn full_names() {
+        let mut names = [
+            name_entry(1, false, "Function"),
+            name_entry(2, false, "SFXGame"),
+            name_entry(3, true, "SFXOnlineComponentUI"),
+            name_entry(4, false, "OnDisplayNotification"),
+        ];
+        let [function_name, package_name, outer_name, object_name] = &mut names;
+
+        let mut function_class = class(name(function_name, 0));
+        let mut package = object(name(package_name, 0), null_mut(), null_mut());
+        let mut outer = object(name(outer_name, 0), &mut *package, null_mut());
+        let function = object(name(object_name, 0), &mut *outer, &mut *function_class);
+
+        assert_eq!(
+            function.get_path_name(),
+            "SFXGame.SFXOnlineComponentUI.OnDisplayNotification"
+        );
+        assert_eq!(
+            function.get_full_name(),
+            "Function SFXGame.SFXOnlineComponentUI.OnDisplayNotification"
+        );
+
+        // Objects without an outer are only their name
+        assert_eq!(package.get_path_name(), "SFXGame");
+
+        // Missing class
+        assert_eq!(outer.get_path_name(), "SFXGame.SFXOnlineComponentUI");
+        assert_eq!(outer.get_full_name(), "(null)");
+
+        // Numeric suffixes are included for each outer
+        outer.name.name_index = 3;
+        assert_eq!(
+            function.get_full_name(),
+            "Function SFXGame.SFXOnlineComponentUI_2.OnDisplayNotification"
+        );
+    }
+
+    #[test]
+    fn full_name_outer_cycle() {
+        let mut entry = name_entry(1, false, "Loop");
+        let mut object = object(name(&mut entry, 0), null_mut(), null_mut());
+        object.outer = &mut *object;
+
+        // Walking stops at the depth limit
+        let path = object.get_path_name();
+        assert_eq!(path.matches("Loop").count(), MAX_OUTER_DEPTH + 1);
+    }
+
+    #[test]
+    fn full_name_cache() {
+        let mut names = [
+            name_entry(1, false, "Class"),
+            name_entry(2, false, "Package"),
+            name_entry(3, false, "Other"),
+            name_entry(4, false, "Object"),
+        ];
+        let [class_name, package_name, other_name, object_name] = &mut names;
+
+        let mut object_class = class(name(class_name, 0));
+        let mut package = object(name(package_name, 0), null_mut(), null_mut());
+        let value = object(name(object_name, 0), &mut *package, &mut *object_class);
+
+        assert_eq!(&*value.get_full_name_cached(), "Class Package.Object");
+        assert_eq!(&*value.get_full_name_cached(), "Class Package.Object");
+
+        // Outer replaced by another object at the same address
+        package.name = name(other_name, 0);
+        assert_eq!(&*value.get_full_name_cached(), "Class Other.Object");
+
+        package.name.name_index = 1;
+        assert_eq!(&*value.get_full_name_cached(), "Class Other_0.Object");
+
+        object_class._base._base._base._base.name = name(package_name, 0);
+        assert_eq!(&*value.get_full_name_cached(), "Package Other_0.Object");
+    }
+
+    /// Borrows the characters as an engine string
+    fn fstring(value: &mut [u16]) -> FString {
+        let count = value.len() as c_int;
+        FString(TArray {
+            data: value.as_mut_ptr().cast(),
+            count,
+            capacity: count,
+            _type: PhantomData,
+        })
+    }
+
+    fn utf16(value: &str) -> Vec<u16> {
+        value.encode_utf16().collect()
+    }
+
+    #[test]
+    fn fstring_nul() {
+        // Trailing terminator isn't part of the string
+        let mut value = utf16("Hello\0");
+        let value = fstring(&mut value);
+        assert_eq!(value.len(), 5);
+        assert_eq!(value.to_string(), "Hello");
+
+        // Characters after an embedded terminator are ignored
+        let mut value = utf16("Hello\0World\0");
+        let value = fstring(&mut value);
+        assert_eq!(value.as_utf16(), utf16("Hello"));
+        assert_eq!(value.to_string_lossy(), "Hello");
+
+        // Strings without a terminator use the whole array
+        let mut value = utf16("Hello");
+        assert_eq!(fstring(&mut value).to_string(), "Hello");
+    }
+
+    #[test]
+    fn fstring_empty() {
+        // No allocation like the engine
+        let value = FString::default();
+        assert!(value.is_empty());
+        assert_eq!(value.to_string(), "");
+        assert_eq!(String::try_from(&value).unwrap(), "");
+
+        // Allocated with only the terminator
+        let mut value = vec![0];
+        let value = fstring(&mut value);
+        assert!(value.is_empty());
+        assert_eq!(value.len(), 0);
+        assert_eq!(value, "");
+        assert_eq!(String::try_from(&value).unwrap(), "");
+    }
+
+    #[test]
+    fn fstring_unpaired_surrogate() {
+        let mut value = vec![b'a' as u16, 0xD800, b'b' as u16, 0];
+        let value = fstring(&mut value);
+
+        assert_eq!(value.to_string(), "a\u{FFFD}b");
+        assert_eq!(value.to_string_lossy(), "a\u{FFFD}b");
+        assert_eq!(format!("{:?}", value), "\"a\u{FFFD}b\"");
+        assert!(String::try_from(&value).is_err());
+
+        // Surrogate pairs are decoded
+        let mut value = utf16("\u{1F600}\0");
+        let value = fstring(&mut value);
+        assert_eq!(value.len(), 2);
+        assert_eq!(String::try_from(&value).unwrap(), "\u{1F600}");
+    }
+
+    #[test]
+    fn fstring_eq() {
+        let mut value = utf16("Caf\u{e9}\0");
+        let value = fstring(&mut value);
+
+        assert_eq!(value, "Caf\u{e9}");
+        assert!(value == *"Caf\u{e9}");
+        assert_ne!(value, "Caf\u{e9}\0");
+        assert_ne!(value, "Cafe");
+        assert_ne!(value, "Caf");
+        assert_ne!(value, "caf\u{e9}");
+
+        let owned = OwnedFString::new("Caf\u{e9}");
+        assert_eq!(owned, "Caf\u{e9}");
+        assert_eq!(owned.as_utf16(), value.as_utf16());
+    }
 }
@@ -0,0 +1,47 @@
+/// Gets the offset of a field within a struct, usable in const contexts
+/// and for fields of packed structs
+macro_rules! offset_of {
+    ($ty:ty, $field:tt) => {{
+        let value = ::std::mem::MaybeUninit::<$ty>::uninit();
+        let base = value.as_ptr();
+
+        // Safety: Only the address of the field is taken, the value is never read
+        #[allow(unused_unsafe)]
+        unsafe {
+            ::std::ptr::addr_of!((*base).$field)
+                .cast::<u8>()
+                .offset_from(base.cast::<u8>()) as usize
+        }
+    }};
+}
+
+/// Asserts the size of a struct and the offsets of its fields at compile
+/// time, the assertions are only checked for 32bit targets which match
+/// the layout of the game
+///
+/// ```ignore
+/// assert_layout!(FSFXOnlineSubscriberEventType, 0x0C {
+///     event_callback: 0x00,
+///     event_type: 0x08,
+/// });
+/// ```
+macro_rules! assert_layout {
+    ($ty:ty, $size:literal { $( $field:tt : $offset:literal ),* $(,)? }) => {
+        #[cfg(target_pointer_width = "32")]
+        const _: () = {
+            assert!(
+                ::std::mem::size_of::<$ty>() == $size,
+                concat!("Size of ", stringify!($ty), " doesn't match")
+            );
+            $(
+                assert!(
+                    $crate::sdk::layout::offset_of!($ty, $field) == $offset,
+                    concat!("Offset of ", stringify!($ty), "::", stringify!($field), " doesn't match")
+                );
+            )*
+        };
+    };
+}
+
+pub(crate) use assert_layout;
+pub(crate) use offset_of;
@@ -0,0 +1,207 @@
+//! Allocation through the game allocator (GMalloc), memory that may be
+//! freed or reallocated by the engine must be allocated here
+
+use super::layout::assert_layout;
+use std::{
+    alloc::Layout,
+    os::raw::{c_ulong, c_void},
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        OnceLock,
+    },
+};
+
+/// Memory address of the GMalloc global, only set when configured
+static GMALLOC_ADDRESS: AtomicUsize = AtomicUsize::new(0);
+
+/// Game allocator chosen on the first allocation, zero when the Rust
+/// allocator is used
+static ALLOCATOR: OnceLock<usize> = OnceLock::new();
+
+/// Sets the memory address of the GMalloc global pointer, must be set
+/// before the first allocation
+pub fn set_gmalloc_address(address: usize) {
+    GMALLOC_ADDRESS.store(address, Ordering::Release);
+}
+
+/// Game allocator interface
+#[repr(C)]
+struct FMalloc {
+    vtable: *const FMallocVTable,
+}
+
+/// Virtual functions of [FMalloc], FExec::Exec followed by the allocation functions
+#[repr(C)]
+struct FMallocVTable {
+    exec: *const c_void,
+    malloc: unsafe extern "thiscall" fn(*mut FMalloc, c_ulong, c_ulong) -> *mut c_void,
+    realloc:
+        unsafe extern "thiscall" fn(*mut FMalloc, *mut c_void, c_ulong, c_ulong) -> *mut c_void,
+    free: unsafe extern "thiscall" fn(*mut FMalloc, *mut c_void),
+}
+
+assert_layout!(FMallocVTable, 0x10 {
+    exec: 0x00,
+    malloc: 0x04,
+    realloc: 0x08,
+    free: 0x0C,
+});
+
+/// Gets the game allocator, [None] if the address isn't configured or the
+/// game hadn't created its allocator at the first allocation. The allocator
+/// is only chosen once so memory is always freed by the allocator that
+/// allocated it
+unsafe fn gmalloc() -> Option<*mut FMalloc> {
+    let allocator = *ALLOCATOR.get_or_init(|| {
+        let address = GMALLOC_ADDRESS.load(Ordering::Acquire);
+        (address as *const *mut FMalloc)
+            .as_ref()
+            .map_or(0, |gmalloc| *gmalloc as usize)
+    });
+
+    (allocator != 0).then_some(allocator as *mut FMalloc)
+}
+
+/// Whether allocations are made through the game allocator, when false the
+/// Rust allocator is used which is only safe for memory the engine never frees
+pub fn is_game_allocator() -> bool {
+    unsafe { gmalloc().is_some() }
+}
+
+/// Allocates `size` bytes, null is returned for zero sizes or when the
+/// allocation fails
+///
+/// # Safety
+///
+/// The memory must be freed with [free] using the same size and alignment
+pub unsafe fn malloc(size: usize, align: usize) -> *mut c_void {
+    if size == 0 {
+        return std::ptr::null_mut();
+    }
+
+    match gmalloc() {
+        Some(gmalloc) => ((*(*gmalloc).vtable).malloc)(gmalloc, size as c_ulong, align as c_ulong),
+        None => match Layout::from_size_align(size, align) {
+            Ok(layout) => std::alloc::alloc(layout).cast(),
+            Err(_) => std::ptr::null_mut(),
+        },
+    }
+}
+
+/// Reallocates the memory to `size` bytes, `old_size` is the size
+/// the memory was allocated with
+///
+/// # Safety
+///
+/// `ptr` must be null or allocated by [malloc] or [realloc] with the
+/// same alignment
+pub unsafe fn realloc(ptr: *mut c_void, old_size: usize, size: usize, align: usize) -> *mut c_void {
+    if ptr.is_null() {
+        return malloc(size, align);
+    }
+
+    if size == 0 {
+        free(ptr, old_size, align);
+        return std::ptr::null_mut();
+    }
+
+    match gmalloc() {
+        Some(gmalloc) => {
+            ((*(*gmalloc).vtable).realloc)(gmalloc, ptr, size as c_ulong, align as c_ulong)
+        }
+        None => match Layout::from_size_align(old_size, align) {
+            Ok(layout) => std::alloc::realloc(ptr.cast(), layout, size).cast(),
+            Err(_) => std::ptr::null_mut(),
+        },
+    }
+}
+
+/// Frees the memory, `size` is the size the memory was allocated with
+///
+/// # Safety
+///
+/// `ptr` must be null or allocated by [malloc] or [realloc] with the
+/// same alignment
+pub unsafe fn free(ptr: *mut c_void, size: usize, align: usize) {
+    if ptr.is_null() {
+        return;
+    }
+
+    match gmalloc() {
+        Some(gmalloc) => ((*(*gmalloc).vtable).free)(gmalloc, ptr),
+        None => {
+            if let Ok(layout) = Layout::from_size_align(size, align) {
+                std::alloc::dealloc(ptr.cast(), layout)
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn fallback_alignment() {
+        for align in [1, 2, 4, 8, 16, 64, 4096] {
+            unsafe {
+                let ptr = malloc(3, align);
+                assert!(!ptr.is_null());
+                assert_eq!(ptr as usize % align, 0, "align {align}");
+
+                let ptr = realloc(ptr, 3, 100, align);
+                assert!(!ptr.is_null());
+                assert_eq!(ptr as usize % align, 0, "align {align}");
+
+                free(ptr, 100, align);
+            }
+        }
+    }
+
+    #[test]
+    fn fallback_realloc() {
+        unsafe {
+            let ptr = malloc(4, 4).cast::<u32>();
+            ptr.write(0xDEADBEEF);
+
+            // Contents are kept when growing and shrinking
+            let ptr = realloc(ptr.cast(), 4, 4096, 4).cast::<u32>();
+            assert_eq!(ptr.read(), 0xDEADBEEF);
+            ptr.add(1023).write(7);
+
+            let ptr = realloc(ptr.cast(), 4096, 8, 4).cast::<u32>();
+            assert_eq!(ptr.read(), 0xDEADBEEF);
+
+            free(ptr.cast(), 8, 4);
+        }
+    }
+
+    #[test]
+    fn null_and_zero_sizes() {
+        unsafe {
+            assert!(malloc(0, 8).is_null());
+
+            // Reallocating null allocates
+            let ptr = realloc(std::ptr::null_mut(), 0, 16, 8);
+            assert!(!ptr.is_null());
+
+            // Reallocating to zero frees
+            assert!(realloc(ptr, 16, 0, 8).is_null());
+
+            free(std::ptr::null_mut(), 0, 8);
+        }
+    }
+
+    #[test]
+    fn invalid_alignment() {
+        unsafe {
+            assert!(malloc(8, 3).is_null());
+            assert!(realloc(std::ptr::null_mut(), 0, 8, 3).is_null());
+        }
+    }
+
+    #[test]
+    fn rust_allocator() {
+        assert!(!is_game_allocator());
+    }
+}
@@ -1,3 +1,6 @@
 pub mod core;
+pub mod layout;
+pub mod memory;
+pub mod params;
 pub mod sfxgame;
 pub mod sfxonlinefoundation;
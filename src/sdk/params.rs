@@ -0,0 +1,137 @@
+/// Parameters block layout for an UnrealScript function, the params
+/// block contains the arguments, out parameters and the return value
+///
+/// # Safety
+///
+/// The implementing type must match the layout of the params block
+/// for [UFunctionParams::FUNCTION]
+pub unsafe trait UFunctionParams: Sized {
+    /// Full name of the function these params belong to
+    const FUNCTION: &'static str;
+
+    /// Return value of the function, `()` for functions without a return value
+    type Return;
+
+    /// Takes the return value from the params block
+    fn into_return(self) -> Self::Return;
+}
+
+/// Declares the params block for an UnrealScript function implementing
+/// [UFunctionParams], a return value slot is added after the arguments
+/// when a return type is provided
+///
+/// ```ignore
+/// define_params! {
+///     pub struct HasCerberusDLCParams = "Function SFXGame.SFXOnlineComponentUI.HasCerberusDLC" {} -> c_ulong
+/// }
+/// ```
+macro_rules! define_params {
+    (
+        $(#[$meta:meta])*
+        $vis:vis struct $name:ident = $function:literal {
+            $( $field:ident : $field_ty:ty ),* $(,)?
+        } -> $ret:ty
+    ) => {
+        $(#[$meta])*
+        #[derive(Debug, Clone, Copy)]
+        #[repr(C, packed(4))]
+        $vis struct $name {
+            $( pub $field: $field_ty, )*
+            pub return_value: $ret,
+        }
+
+        unsafe impl $crate::sdk::params::UFunctionParams for $name {
+            const FUNCTION: &'static str = $function;
+
+            type Return = $ret;
+
+            fn into_return(self) -> Self::Return {
+                self.return_value
+            }
+        }
+    };
+
+    (
+        $(#[$meta:meta])*
+        $vis:vis struct $name:ident = $function:literal {
+            $( $field:ident : $field_ty:ty ),* $(,)?
+        }
+    ) => {
+        $(#[$meta])*
+        #[derive(Debug, Clone, Copy)]
+        #[repr(C, packed(4))]
+        $vis struct $name {
+            $( pub $field: $field_ty, )*
+        }
+
+        unsafe impl $crate::sdk::params::UFunctionParams for $name {
+            const FUNCTION: &'static str = $function;
+
+            type Return = ();
+
+            fn into_return(self) -> Self::Return {}
+        }
+    };
+}
+
+/// Defines a method that calls an UnrealScript function through
+/// process event using the provided [UFunctionParams] type, the function
+/// object is found using the full name from [UFunctionParams::FUNCTION].
+///
+/// Arguments are listed after the params type, `out` parameters are listed
+/// after a `;` and are taken as mutable references that are written back
+/// once the function returns. The method returns [UFunctionParams::Return]
+/// or an error if the function object couldn't be found
+///
+/// ```ignore
+/// define_method!(get_state, GetStateParams, index: c_int; out state: c_int);
+/// ```
+macro_rules! define_method {
+    (
+        $func_name:ident, $params:ident
+        $(, $arg_name:ident : $arg_type:ty )*
+        $(; out $( $out_name:ident : $out_type:ty ),+ )?
+    ) => {
+        /// # Safety
+        ///
+        /// The object must be a valid game object of this class
+        pub unsafe fn $func_name(
+            &mut self,
+            $( $arg_name: $arg_type, )*
+            $($( $out_name: &mut $out_type, )+)?
+        ) -> Result<
+            <$params as $crate::sdk::params::UFunctionParams>::Return,
+            $crate::sdk::core::SdkError,
+        > {
+            static mut FN_PTR: *mut $crate::sdk::core::UFunction = ::std::ptr::null_mut();
+
+            // Find the function object if not initialized
+            if FN_PTR.is_null() {
+                FN_PTR = $crate::sdk::core::find_function(
+                    <$params as $crate::sdk::params::UFunctionParams>::FUNCTION,
+                )?;
+            }
+
+            // Create the function params, the return value starts zeroed
+            let mut params: $params = ::std::mem::zeroed();
+            $( params.$arg_name = $arg_name; )*
+            // Out params are also passed into the function
+            $($( params.$out_name = *$out_name; )+)?
+
+            $crate::process_event(
+                self as *const _ as *mut _,
+                FN_PTR,
+                &mut params as *const _ as *mut _,
+                ::std::ptr::null_mut(),
+            );
+
+            // Read back the out params from the params block
+            $($( *$out_name = params.$out_name; )+)?
+
+            Ok($crate::sdk::params::UFunctionParams::into_return(params))
+        }
+    };
+}
+
+pub(crate) use define_method;
+pub(crate) use define_params;
@@ -1,77 +1,125 @@
-use super::{core::FString, sfxonlinefoundation::USFXOnlineComponent};
-use crate::{
-    process_event,
-    sdk::core::{get_function_object, UFunction},
+//! Package SFXGame, generated by sdk-gen from dump.json. Don't edit by hand
+
+use super::{
+    core::{FName, FPointer, FScriptDelegate, FString, StaticClass},
+    layout::assert_layout,
+    params::{define_method, define_params},
+    sfxonlinefoundation::USFXOnlineComponent,
 };
-use std::ptr::null_mut;
+use std::os::raw::{c_int, c_uchar, c_ulong, c_void};
 
-macro_rules! define_method {
-    ($func_name:ident, $fn_index:expr, $( $arg_name:ident : $arg_type:ty ),*) => {
-        pub unsafe fn $func_name(
-            &mut self,
-            $( $arg_name: $arg_type ),*
-        ) {
-            /// Generated structure to hold the function params
-            #[derive(Debug, Clone, Copy)]
-            #[repr(C)]
-            #[allow(non_camel_case_types)]
-            struct Params {
-                $( $arg_name: $arg_type ),*
-            }
+// ScriptStruct SFXGame.SFXOnlineMOTDInfo
+// 0x0038 (0x0038 - 0x0000)
+#[derive(Debug, Clone, Copy)]
+#[repr(C, packed(4))]
+pub struct FSFXOnlineMOTDInfo {
+    /// 0x0000 (0x000C) [0x0000000000000000] str Message
+    pub message: FString,
+    /// 0x000C (0x000C) [0x0000000000000000] str Title
+    pub title: FString,
+    /// 0x0018 (0x000C) [0x0000000000000000] str Image
+    pub image: FString,
+    /// 0x0024 (0x0004) [0x0000000000000000] int TrackingID
+    pub tracking_id: c_int,
+    /// 0x0028 (0x0004) [0x0000000000000000] int Priority
+    pub priority: c_int,
+    /// 0x002C (0x0004) [0x0000000000000000] int BWEntId
+    pub bw_ent_id: c_int,
+    /// 0x0030 (0x0004) [0x0000000000000000] int OfferID
+    pub offer_id: c_int,
+    /// 0x0034 (0x0001) [0x0000000000000000] enum:SFXOnlineConnection_MessageType Type
+    pub ty: c_uchar,
+}
 
-            static mut FN_PTR: *mut UFunction = null_mut();
+assert_layout!(FSFXOnlineMOTDInfo, 0x38 {
+    message: 0x00,
+    title: 0x0C,
+    image: 0x18,
+    tracking_id: 0x24,
+    priority: 0x28,
+    bw_ent_id: 0x2C,
+    offer_id: 0x30,
+    ty: 0x34,
+});
 
-            // Create the function object pointer if not initialized
-            if FN_PTR.is_null() {
-                let missing_class_error = format!("Missing {} ({}) function object", stringify!($func_name), stringify!($fn_index));
+// Class SFXGame.SFXOnlineComponentUI
+// 0x0058 (0x00BC - 0x0064)
+#[derive(Debug, Clone, Copy)]
+#[repr(C, packed(4))]
+pub struct USFXOnlineComponentUI {
+    pub _base: USFXOnlineComponent,
+    /// 0x0064 (0x0004) [0x0000000000801002] pointer VfTable_IISFXOnlineComponentUserInterface
+    pub vf_table_iisfx_online_component_user_interface: FPointer,
+    /// 0x0068 (0x000C) [0x0000000000400000] delegate __ExternalCallback_OnDisplayNotification__Delegate
+    pub __external_callback_on_display_notification_delegate: FScriptDelegate,
+    /// 0x0074 (0x000C) [0x0000000000400000] delegate __ExternalCallback_ClearNotifications__Delegate
+    pub __external_callback_clear_notifications_delegate: FScriptDelegate,
+    /// 0x0080 (0x000C) [0x0000000000400000] delegate __ExternalCallback_SetState__Delegate
+    pub __external_callback_set_state_delegate: FScriptDelegate,
+    /// 0x008C (0x000C) [0x0000000000400000] delegate __ExternalCallback_CloseEANetworking__Delegate
+    pub __external_callback_close_ea_networking_delegate: FScriptDelegate,
+    /// 0x0098 (0x000C) [0x0000000000400000] delegate __ExternalCallback_HasCerberusDLC__Delegate
+    pub __external_callback_has_cerberus_dlc_delegate: FScriptDelegate,
+    /// 0x00A4 (0x000C) [0x0000000000400000] delegate __ExternalCallback_ShowStore__Delegate
+    pub __external_callback_show_store_delegate: FScriptDelegate,
+    /// 0x00B0 (0x0008) [0x0000000000000000] name HandlerId
+    pub handler_id: FName,
+    /// 0x00B8 (0x0004) [0x0000000000000000] object:SFXSFHandler_EANetworking m_oGUI
+    pub m_o_gui: *mut c_void,
+}
 
-                FN_PTR = get_function_object($fn_index).expect(&missing_class_error);
-            }
+assert_layout!(USFXOnlineComponentUI, 0xBC {
+    _base: 0x00,
+    vf_table_iisfx_online_component_user_interface: 0x64,
+    __external_callback_on_display_notification_delegate: 0x68,
+    __external_callback_clear_notifications_delegate: 0x74,
+    __external_callback_set_state_delegate: 0x80,
+    __external_callback_close_ea_networking_delegate: 0x8C,
+    __external_callback_has_cerberus_dlc_delegate: 0x98,
+    __external_callback_show_store_delegate: 0xA4,
+    handler_id: 0xB0,
+    m_o_gui: 0xB8,
+});
 
-            // Create the function params
-            let mut params = Params {
-                $( $arg_name ),*
-            };
+unsafe impl StaticClass for USFXOnlineComponentUI {
+    const NAME: &'static str = "SFXOnlineComponentUI";
+}
 
-            process_event(
-                self as *const _ as *mut _,
-                FN_PTR,
-                &mut params as *const _ as *mut _,
-                std::ptr::null_mut(),
-            );
-        }
-    };
+define_params! {
+    /// Params for [USFXOnlineComponentUI::event_on_display_notification]
+    ///
+    /// GObjects index 78599 when dumped
+    pub struct OnDisplayNotificationParams = "Function SFXGame.SFXOnlineComponentUI.OnDisplayNotification" {
+        info: FSFXOnlineMOTDInfo,
+    }
 }
 
-#[derive(Debug, Clone, Copy)]
-#[repr(C, packed(4))]
-pub struct USFXOnlineComponentUI {
-    // Base C++ class for this class
-    pub _base: USFXOnlineComponent,
-    // struct FPointer                                    VfTable_IISFXOnlineComponentUserInterface;        		// 0x0064 (0x0004) [0x0000000000801002]              ( CPF_Const | CPF_Native | CPF_NoExport )
-    // struct FScriptDelegate                             __ExternalCallback_OnDisplayNotification__Delegate;		// 0x0068 (0x000C) [0x0000000000400000]              ( CPF_NeedCtorLink )
-    // struct FScriptDelegate                             __ExternalCallback_ClearNotifications__Delegate;  		// 0x0074 (0x000C) [0x0000000000400000]              ( CPF_NeedCtorLink )
-    // struct FScriptDelegate                             __ExternalCallback_SetState__Delegate;            		// 0x0080 (0x000C) [0x0000000000400000]              ( CPF_NeedCtorLink )
-    // struct FScriptDelegate                             __ExternalCallback_CloseEANetworking__Delegate;   		// 0x008C (0x000C) [0x0000000000400000]              ( CPF_NeedCtorLink )
-    // struct FScriptDelegate                             __ExternalCallback_HasCerberusDLC__Delegate;      		// 0x0098 (0x000C) [0x0000000000400000]              ( CPF_NeedCtorLink )
-    // struct FScriptDelegate                             __ExternalCallback_ShowStore__Delegate;           		// 0x00A4 (0x000C) [0x0000000000400000]              ( CPF_NeedCtorLink )
-    // struct FName                                       HandlerId;                                        		// 0x00B0 (0x0008) [0x0000000000000000]
-    // class USFXSFHandler_EANetworking*                  m_oGUI;
+assert_layout!(OnDisplayNotificationParams, 0x38 {
+    info: 0x00,
+});
+
+define_params! {
+    /// Params for [USFXOnlineComponentUI::clear_notifications]
+    pub struct ClearNotificationsParams = "Function SFXGame.SFXOnlineComponentUI.ClearNotifications" {}
 }
 
-impl USFXOnlineComponentUI {
-    define_method!(event_on_display_notification, 78599, info: FSFXOnlineMOTDInfo);
+assert_layout!(ClearNotificationsParams, 0x00 {});
+
+define_params! {
+    /// Params for [USFXOnlineComponentUI::has_cerberus_dlc]
+    pub struct HasCerberusDLCParams = "Function SFXGame.SFXOnlineComponentUI.HasCerberusDLC" {} -> c_ulong
 }
 
-#[derive(Debug, Clone, Copy)]
-#[repr(C, packed(4))]
-pub struct FSFXOnlineMOTDInfo {
-    pub message: FString,
-    pub title: FString,
-    pub image: FString,
-    pub tracking_id: ::std::os::raw::c_int,
-    pub priority: ::std::os::raw::c_int,
-    pub bw_ent_id: ::std::os::raw::c_int,
-    pub offer_id: ::std::os::raw::c_int,
-    pub ty: ::std::os::raw::c_uchar,
+assert_layout!(HasCerberusDLCParams, 0x04 {
+    return_value: 0x00,
+});
+
+impl USFXOnlineComponentUI {
+    define_method!(
+        event_on_display_notification,
+        OnDisplayNotificationParams,
+        info: FSFXOnlineMOTDInfo
+    );
+    define_method!(clear_notifications, ClearNotificationsParams);
+    define_method!(has_cerberus_dlc, HasCerberusDLCParams);
 }
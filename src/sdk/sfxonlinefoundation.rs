@@ -1,5 +1,43 @@
-use super::core::{FName, FScriptDelegate, TArray, UObject};
-use std::os::raw::{c_ulong, c_void};
+//! Package SFXOnlineFoundation, generated by sdk-gen from dump.json. Don't edit by hand
+
+use super::{
+    core::{FName, FScriptDelegate, StaticClass, TArray, UObject},
+    layout::assert_layout,
+};
+use std::os::raw::{c_uchar, c_ulong, c_void};
+
+// Enum SFXOnlineFoundation.SFXOnlineConnection_MessageType
+#[allow(non_camel_case_types)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[repr(u8)]
+pub enum ESFXOnlineConnection_MessageType {
+    SFXONLINE_MT_MESSAGEOFTHEDAY = 0,
+    SFXONLINE_MT_DOWNLOAD_PROMPT = 1,
+    SFXONLINE_MT_GAW_SUMMARY = 2,
+    SFXONLINE_MT_GAW_STATUS_UPDATE = 3,
+    SFXONLINE_MT_FRIEND_ACHIVEMENT = 4,
+    SFXONLINE_MT_FRIEND_LEADERBOARD_RANK_CHANGE = 5,
+    SFXONLINE_MT_MESSAGEOFTHEDAY_TICKERONLY = 6,
+    SFXONLINE_MT_DISCONNECTED_TICKERONLY = 7,
+    SFXONLINE_MT_MP_PROMO = 8,
+    SFXONLINE_MT_MAX = 9,
+}
+
+// ScriptStruct SFXOnlineFoundation.SFXOnlineSubscriberEventType
+// 0x000C (0x000C - 0x0000)
+#[derive(Debug, Clone, Copy)]
+#[repr(C, packed(4))]
+pub struct FSFXOnlineSubscriberEventType {
+    /// 0x0000 (0x0008) [0x0000000000000000] name EventCallback
+    pub event_callback: FName,
+    /// 0x0008 (0x0001) [0x0000000000000000] byte EventType
+    pub event_type: c_uchar,
+}
+
+assert_layout!(FSFXOnlineSubscriberEventType, 0x0C {
+    event_callback: 0x00,
+    event_type: 0x08,
+});
 
 // Class SFXOnlineFoundation.SFXOnlineComponent
 // 0x0028 (0x0064 - 0x003C)
@@ -7,18 +45,27 @@ use std::os::raw::{c_ulong, c_void};
 #[repr(C, packed(4))]
 pub struct USFXOnlineComponent {
     pub _base: UObject,
-
-    pub event_subscriber_table:
-        TArray<FSFXOnlineSubscriberEventType /* FSFXOnlineSubscriberEventType */>,
+    /// 0x003C (0x000C) [0x0000000000400000] array<struct:SFXOnlineSubscriberEventType> EventSubscriberTable
+    pub event_subscriber_table: TArray<FSFXOnlineSubscriberEventType>,
+    /// 0x0048 (0x000C) [0x0000000000400000] delegate __OnEvent__Delegate
     pub __on_event_delegate: FScriptDelegate,
+    /// 0x0054 (0x0008) [0x0000000000000000] name APIName
     pub api_name: FName,
-    pub online_subsystem: *mut c_void, /* USFXOnlineSubsystem */
+    /// 0x005C (0x0004) [0x0000000000000000] object:SFXOnlineSubsystem OnlineSubsystem
+    pub online_subsystem: *mut c_void,
+    /// 0x0060 (0x0004) [0x0000000000000000] bool bNeedsStateMachine (0x00000001)
     pub needs_state_machine: c_ulong,
 }
 
-#[derive(Debug, Clone, Copy)]
-#[repr(C, packed(4))]
-pub struct FSFXOnlineSubscriberEventType {
-    pub event_callback: FName,
-    pub event_type: ::std::os::raw::c_uchar,
+assert_layout!(USFXOnlineComponent, 0x64 {
+    _base: 0x00,
+    event_subscriber_table: 0x3C,
+    __on_event_delegate: 0x48,
+    api_name: 0x54,
+    online_subsystem: 0x5C,
+    needs_state_machine: 0x60,
+});
+
+unsafe impl StaticClass for USFXOnlineComponent {
+    const NAME: &'static str = "SFXOnlineComponent";
 }
@@ -0,0 +1,358 @@
+//! Tracing of processed events to a JSONL file with filtering,
+//! sampling and per-function hit counters
+
+use crate::{
+    config::{game_relative_path, EventsConfig},
+    error,
+    sdk::core::{UFunction, UObject},
+    warn,
+    writer::{lock_for_close, BackgroundWriter},
+};
+use parking_lot::Mutex;
+use serde::Serialize;
+use std::{
+    collections::HashMap,
+    fs::File,
+    io::BufWriter,
+    path::PathBuf,
+    sync::Arc,
+    time::{SystemTime, UNIX_EPOCH},
+};
+use windows_sys::Win32::System::Threading::GetCurrentThreadId;
+
+/// Active tracer, [None] when tracing is disabled
+static TRACER: Mutex<Option<Tracer>> = Mutex::new(None);
+
+/// Glob pattern supporting `*` (any sequence) and `?` (any single character)
+#[derive(Debug, Clone)]
+pub struct Glob(Vec<char>);
+
+impl Glob {
+    pub fn new(pattern: &str) -> Self {
+        Self(pattern.chars().collect())
+    }
+
+    /// Checks if the whole of the value matches the pattern
+    pub fn matches(&self, value: &str) -> bool {
+        let value: Vec<char> = value.chars().collect();
+        let pattern = &self.0;
+
+        let (mut p, mut v) = (0, 0);
+        // Position of the last `*` and the value position it was matched at
+        let mut backtrack: Option<(usize, usize)> = None;
+
+        while v < value.len() {
+            match pattern.get(p) {
+                Some('*') => {
+                    backtrack = Some((p, v));
+                    p += 1;
+                }
+                Some('?') => {
+                    p += 1;
+                    v += 1;
+                }
+                Some(c) if *c == value[v] => {
+                    p += 1;
+                    v += 1;
+                }
+                // Mismatch, let the last `*` consume another character
+                _ => match backtrack {
+                    Some((star, matched)) => {
+                        p = star + 1;
+                        v = matched + 1;
+                        backtrack = Some((star, matched + 1));
+                    }
+                    None => return false,
+                },
+            }
+        }
+
+        // Remaining pattern must only be `*`
+        pattern[p..].iter().all(|c| *c == '*')
+    }
+}
+
+/// Include and exclude filters for function names
+pub struct TraceFilter {
+    include: Vec<Glob>,
+    exclude: Vec<Glob>,
+}
+
+impl TraceFilter {
+    pub fn new(include: &[String], exclude: &[String]) -> Self {
+        Self {
+            include: include.iter().map(|value| Glob::new(value)).collect(),
+            exclude: exclude.iter().map(|value| Glob::new(value)).collect(),
+        }
+    }
+
+    /// Checks whether the function full name passes the filters
+    pub fn is_included(&self, name: &str) -> bool {
+        (self.include.is_empty() || self.include.iter().any(|glob| glob.matches(name)))
+            && !self.exclude.iter().any(|glob| glob.matches(name))
+    }
+}
+
+/// Checks whether the call with the hit count is traced, the first call
+/// and every `sample_rate` calls after it are traced
+fn is_sampled(hits: u64, sample_rate: u64) -> bool {
+    (hits - 1) % sample_rate == 0
+}
+
+/// Cached details about a function that has been processed
+struct FunctionStats {
+    /// Full name of the function
+    name: String,
+    /// Whether the function passes the trace filters
+    included: bool,
+    /// Number of times the function has been processed
+    hits: u64,
+}
+
+/// Single traced event written as a JSON line
+#[derive(Serialize)]
+struct TraceRecord<'a> {
+    /// Unix timestamp in microseconds
+    timestamp: u64,
+    /// ID of the thread that processed the event
+    thread: u32,
+    /// Full name of the object the event was processed on
+    object: &'a str,
+    /// Full name of the function
+    function: &'a str,
+    /// Number of times the function had been processed including this call
+    hit: u64,
+}
+
+/// Hit count entry written to the counts file
+#[derive(Serialize)]
+struct HitCount<'a> {
+    function: &'a str,
+    hits: u64,
+}
+
+pub struct Tracer {
+    output: BackgroundWriter,
+    filter: TraceFilter,
+    sample_rate: u64,
+    counts_path: Option<PathBuf>,
+    /// Stats for each function keyed by the function address
+    functions: HashMap<usize, FunctionStats>,
+}
+
+impl Tracer {
+    pub fn new(config: &EventsConfig) -> std::io::Result<Self> {
+        let file = File::create(game_relative_path(&config.path))?;
+
+        Ok(Self {
+            output: BackgroundWriter::new(file, config.buffer_capacity)?,
+            filter: TraceFilter::new(&config.include, &config.exclude),
+            sample_rate: config.sample_rate.max(1) as u64,
+            counts_path: config.counts_path.as_deref().map(game_relative_path),
+            functions: HashMap::new(),
+        })
+    }
+
+    /// Records a processed event
+    ///
+    /// # Safety
+    ///
+    /// `object` and `function` must be valid pointers
+    unsafe fn record(&mut self, object: *mut UObject, function: *mut UFunction) {
+        let stats = self.functions.entry(function as usize).or_insert_with(|| {
+            let name = function.read().as_object_ref().get_full_name();
+            let included = self.filter.is_included(&name);
+            FunctionStats {
+                name,
+                included,
+                hits: 0,
+            }
+        });
+
+        stats.hits += 1;
+
+        if !stats.included || !is_sampled(stats.hits, self.sample_rate) {
+            return;
+        }
+
+        let object_name = object
+            .as_ref()
+            .map(|object| object.get_full_name_cached())
+            .unwrap_or_else(|| Arc::from("(null)"));
+
+        let timestamp = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|value| value.as_micros() as u64)
+            .unwrap_or_default();
+
+        let record = TraceRecord {
+            timestamp,
+            thread: GetCurrentThreadId(),
+            object: &object_name,
+            function: &stats.name,
+            hit: stats.hits,
+        };
+
+        if let Ok(mut line) = serde_json::to_vec(&record) {
+            line.push(b'\n');
+            self.output.push(line);
+        }
+    }
+
+    /// Flushes the trace output and writes the hit counts file, see
+    /// [BackgroundWriter::close] for `join`
+    fn close(self, join: bool) {
+        let dropped = self.output.dropped();
+        if dropped > 0 {
+            warn!("Dropped {} trace records, trace buffer was full", dropped);
+        }
+
+        self.output.close(join);
+
+        let path = match self.counts_path {
+            Some(value) => value,
+            None => return,
+        };
+
+        let mut counts: Vec<HitCount> = self
+            .functions
+            .values()
+            .map(|stats| HitCount {
+                function: &stats.name,
+                hits: stats.hits,
+            })
+            .collect();
+        counts.sort_by(|a, b| b.hits.cmp(&a.hits));
+
+        let result = File::create(&path)
+            .map_err(serde_json::Error::io)
+            .and_then(|file| serde_json::to_writer_pretty(BufWriter::new(file), &counts));
+
+        if let Err(err) = result {
+            error!("Failed to write event counts {}: {}", path.display(), err);
+        }
+    }
+}
+
+/// Starts tracing events using the provided config
+pub fn init(config: &EventsConfig) {
+    if !config.dump {
+        return;
+    }
+
+    match Tracer::new(config) {
+        Ok(tracer) => *TRACER.lock() = Some(tracer),
+        Err(err) => error!("Failed to create event dump {}: {}", config.path, err),
+    }
+}
+
+/// Records a processed event if tracing is enabled
+///
+/// # Safety
+///
+/// `object` and `function` must be valid pointers
+pub unsafe fn record(object: *mut UObject, function: *mut UFunction) {
+    if let Some(tracer) = TRACER.lock().as_mut() {
+        tracer.record(object, function);
+    }
+}
+
+/// Stops tracing, flushing the output and writing the hit counts. When
+/// not joining nothing is written if the tracer is locked by another
+/// thread, see [lock_for_close]
+pub fn close(join: bool) {
+    let Some(mut tracer) = lock_for_close(&TRACER, join) else {
+        return;
+    };
+
+    if let Some(tracer) = tracer.take() {
+        tracer.close(join);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn glob() {
+        let cases: &[(&str, &str, bool)] = &[
+            ("", "", true),
+            ("", "a", false),
+            ("abc", "abc", true),
+            ("abc", "abcd", false),
+            ("abc", "ab", false),
+            ("*", "", true),
+            ("*", "anything", true),
+            ("**", "anything", true),
+            ("*Tick", "Function Engine.Actor.Tick", true),
+            ("*Tick", "Function Engine.Actor.TickSpecial", false),
+            ("Function Engine.*", "Function Engine.Actor.Tick", true),
+            ("Function Engine.*", "Function SFXGame.Actor.Tick", false),
+            ("Function *.Actor.*", "Function Engine.Actor.Tick", true),
+            ("Function *.Actor.*", "Function Engine.Pawn.Tick", false),
+            (
+                "Function Engine.**.Tick",
+                "Function Engine.Actor.Tick",
+                true,
+            ),
+            ("*a*b*", "xxaxxbxx", true),
+            ("*a*b*", "xxbxxaxx", false),
+            ("*aab", "aaaab", true),
+            ("?", "a", true),
+            ("?", "", false),
+            ("?", "ab", false),
+            ("T?ck", "Tick", true),
+            ("T?ck", "Tck", false),
+            ("*?", "", false),
+            ("*?", "a", true),
+        ];
+
+        for (pattern, value, expected) in cases {
+            assert_eq!(
+                Glob::new(pattern).matches(value),
+                *expected,
+                "{:?} against {:?}",
+                pattern,
+                value
+            );
+        }
+    }
+
+    #[test]
+    fn filter() {
+        let filter = TraceFilter::new(&[], &[]);
+        assert!(filter.is_included("Function Engine.Actor.Tick"));
+
+        let filter = TraceFilter::new(
+            &["Function Engine.*".to_string(), "*Notification".to_string()],
+            &[],
+        );
+        assert!(filter.is_included("Function Engine.Actor.Tick"));
+        assert!(filter.is_included("Function SFXGame.SFXOnlineComponentUI.ClearNotification"));
+        assert!(!filter.is_included("Function SFXGame.SFXPawn.Tick"));
+
+        // Exclude takes priority over include
+        let filter = TraceFilter::new(&["Function Engine.*".to_string()], &["*.Tick".to_string()]);
+        assert!(filter.is_included("Function Engine.Actor.Touch"));
+        assert!(!filter.is_included("Function Engine.Actor.Tick"));
+
+        let filter = TraceFilter::new(&[], &["*.Tick".to_string()]);
+        assert!(filter.is_included("Function SFXGame.SFXPawn.Touch"));
+        assert!(!filter.is_included("Function SFXGame.SFXPawn.Tick"));
+    }
+
+    #[test]
+    fn sampling() {
+        let sampled = |sample_rate| -> Vec<u64> {
+            (1..=10)
+                .filter(|hits| is_sampled(*hits, sample_rate))
+                .collect()
+        };
+
+        assert_eq!(sampled(1), (1..=10).collect::<Vec<_>>());
+        assert_eq!(sampled(3), [1, 4, 7, 10]);
+        assert_eq!(sampled(10), [1]);
+        assert_eq!(sampled(20), [1]);
+    }
+}
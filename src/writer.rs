@@ -0,0 +1,302 @@
+//! Buffered writer that moves file I/O off the game thread, lines are
+//! pushed into a bounded buffer and written in batches by a writer thread
+
+use parking_lot::{Condvar, Mutex, MutexGuard};
+use std::{
+    collections::VecDeque,
+    io::{BufWriter, Write},
+    mem::take,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc,
+    },
+    thread::JoinHandle,
+    time::Duration,
+};
+
+/// Maximum time buffered lines wait before being written
+const FLUSH_INTERVAL: Duration = Duration::from_millis(250);
+
+/// Maximum time locks are waited on when closing without joining
+const CLOSE_LOCK_TIMEOUT: Duration = Duration::from_millis(100);
+
+/// Locks the mutex while closing. When not joining the process may be
+/// terminating with the lock held by a thread that was already killed,
+/// so the lock is only waited on for [CLOSE_LOCK_TIMEOUT]
+pub fn lock_for_close<T>(mutex: &Mutex<T>, join: bool) -> Option<MutexGuard<'_, T>> {
+    if join {
+        Some(mutex.lock())
+    } else {
+        mutex.try_lock_for(CLOSE_LOCK_TIMEOUT)
+    }
+}
+
+/// State shared between the writer handle and the writer thread
+struct Shared {
+    /// Lines waiting to be written
+    buffer: Mutex<Buffer>,
+    /// Notified when lines are pushed or the writer is closed
+    condvar: Condvar,
+    /// Output, locked while a batch is taken from the buffer and written
+    /// so batches are always written in order
+    output: Mutex<BufWriter<Box<dyn Write + Send>>>,
+    /// Number of lines dropped because the buffer was full
+    dropped: AtomicU64,
+}
+
+struct Buffer {
+    lines: VecDeque<Vec<u8>>,
+    capacity: usize,
+    closed: bool,
+}
+
+/// Writes the lines to the output and flushes it
+fn write_lines(output: &mut impl Write, lines: VecDeque<Vec<u8>>) {
+    for line in lines {
+        _ = output.write_all(&line);
+    }
+    _ = output.flush();
+}
+
+/// Handle to a background writer
+pub struct BackgroundWriter {
+    shared: Arc<Shared>,
+    thread: Option<JoinHandle<()>>,
+}
+
+impl BackgroundWriter {
+    /// Creates a writer for the output that buffers up to `capacity` lines
+    pub fn new(output: impl Write + Send + 'static, capacity: usize) -> std::io::Result<Self> {
+        let shared = Arc::new(Shared {
+            buffer: Mutex::new(Buffer {
+                lines: VecDeque::with_capacity(capacity),
+                capacity,
+                closed: false,
+            }),
+            condvar: Condvar::new(),
+            output: Mutex::new(BufWriter::new(Box::new(output))),
+            dropped: AtomicU64::new(0),
+        });
+
+        let thread = std::thread::Builder::new()
+            .name("deep-link-writer".to_string())
+            .spawn({
+                let shared = shared.clone();
+                move || run_writer(shared)
+            })?;
+
+        Ok(Self {
+            shared,
+            thread: Some(thread),
+        })
+    }
+
+    /// Pushes a line into the buffer, the line is dropped and false
+    /// is returned if the buffer is full
+    pub fn push(&self, line: Vec<u8>) -> bool {
+        let mut buffer = self.shared.buffer.lock();
+
+        if buffer.closed || buffer.lines.len() >= buffer.capacity {
+            self.shared.dropped.fetch_add(1, Ordering::Relaxed);
+            return false;
+        }
+
+        buffer.lines.push_back(line);
+
+        // Wake the writer early once half the buffer is used
+        if buffer.lines.len() >= buffer.capacity / 2 {
+            self.shared.condvar.notify_one();
+        }
+
+        true
+    }
+
+    /// Number of lines dropped because the buffer was full
+    pub fn dropped(&self) -> u64 {
+        self.shared.dropped.load(Ordering::Relaxed)
+    }
+
+    /// Closes the writer writing any remaining lines.
+    ///
+    /// When `join` is true the writer thread writes the remaining lines and
+    /// is joined. It must not be joined while the loader lock is held (From
+    /// within DllMain), otherwise the remaining lines are written on the
+    /// calling thread and are discarded if the locks can't be acquired, see
+    /// [lock_for_close]
+    pub fn close(mut self, join: bool) {
+        if join {
+            self.shared.buffer.lock().closed = true;
+            self.shared.condvar.notify_one();
+
+            if let Some(thread) = self.thread.take() {
+                _ = thread.join();
+            }
+            return;
+        }
+
+        // Output is locked first so a batch already taken by the writer
+        // thread is written before the remaining lines
+        let Some(mut output) = lock_for_close(&self.shared.output, join) else {
+            return;
+        };
+        let Some(mut buffer) = lock_for_close(&self.shared.buffer, join) else {
+            return;
+        };
+        buffer.closed = true;
+        let remaining = take(&mut buffer.lines);
+        drop(buffer);
+
+        self.shared.condvar.notify_one();
+        write_lines(&mut *output, remaining);
+    }
+}
+
+/// Writer thread loop, waits for lines and writes them in batches
+fn run_writer(shared: Arc<Shared>) {
+    loop {
+        {
+            let mut buffer = shared.buffer.lock();
+            if buffer.lines.is_empty() && !buffer.closed {
+                shared.condvar.wait_for(&mut buffer, FLUSH_INTERVAL);
+            }
+        }
+
+        // Lines are taken while holding the output so they're written in order
+        let mut output = shared.output.lock();
+        let (lines, closed) = {
+            let mut buffer = shared.buffer.lock();
+            (take(&mut buffer.lines), buffer.closed)
+        };
+
+        if !lines.is_empty() {
+            write_lines(&mut *output, lines);
+        }
+
+        if closed {
+            break;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Output shared with the test
+    #[derive(Clone, Default)]
+    struct SharedOutput(Arc<Mutex<Vec<u8>>>);
+
+    impl Write for SharedOutput {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            self.0.lock().extend_from_slice(buf);
+            Ok(buf.len())
+        }
+
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+
+    impl SharedOutput {
+        fn lines(&self) -> Vec<String> {
+            String::from_utf8(self.0.lock().clone())
+                .unwrap()
+                .lines()
+                .map(str::to_string)
+                .collect()
+        }
+    }
+
+    fn line(value: usize) -> Vec<u8> {
+        format!("{}\n", value).into_bytes()
+    }
+
+    fn expected(range: std::ops::Range<usize>) -> Vec<String> {
+        range.map(|value| value.to_string()).collect()
+    }
+
+    #[test]
+    fn close_join_writes_in_order() {
+        let output = SharedOutput::default();
+        let writer = BackgroundWriter::new(output.clone(), 10_000).unwrap();
+
+        for value in 0..5000 {
+            assert!(writer.push(line(value)));
+        }
+
+        writer.close(true);
+        assert_eq!(output.lines(), expected(0..5000));
+    }
+
+    #[test]
+    fn close_without_join_writes_in_order() {
+        for _ in 0..20 {
+            let output = SharedOutput::default();
+            let writer = BackgroundWriter::new(output.clone(), 1000).unwrap();
+
+            // Writer thread is woken at half capacity so batches are taken
+            // while lines are still being pushed
+            for value in 0..1000 {
+                assert!(writer.push(line(value)));
+            }
+
+            writer.close(false);
+            assert_eq!(output.lines(), expected(0..1000));
+        }
+    }
+
+    #[test]
+    fn capacity_drops() {
+        let output = SharedOutput::default();
+        let writer = BackgroundWriter::new(output.clone(), 4).unwrap();
+
+        // Hold the output so the writer thread can't take the lines
+        let accepted: Vec<bool> = {
+            let _output = writer.shared.output.lock();
+            (0..6).map(|value| writer.push(line(value))).collect()
+        };
+        assert_eq!(accepted, [true, true, true, true, false, false]);
+        assert_eq!(writer.dropped(), 2);
+
+        writer.close(true);
+        assert_eq!(output.lines(), expected(0..4));
+    }
+
+    #[test]
+    fn push_after_close() {
+        let output = SharedOutput::default();
+        let writer = BackgroundWriter::new(output.clone(), 4).unwrap();
+
+        // Closed by the writer thread exiting or a timed out close
+        writer.shared.buffer.lock().closed = true;
+        assert!(!writer.push(line(1)));
+        assert_eq!(writer.dropped(), 1);
+
+        writer.close(true);
+        assert!(output.lines().is_empty());
+    }
+
+    #[test]
+    fn periodic_flush() {
+        let output = SharedOutput::default();
+        let writer = BackgroundWriter::new(output.clone(), 100).unwrap();
+        assert!(writer.push(line(1)));
+
+        // Below half capacity so only written after the flush interval
+        let start = std::time::Instant::now();
+        while output.lines().is_empty() {
+            assert!(start.elapsed() < FLUSH_INTERVAL * 20, "line wasn't flushed");
+            std::thread::sleep(Duration::from_millis(10));
+        }
+        assert_eq!(output.lines(), ["1"]);
+
+        writer.close(true);
+    }
+
+    #[test]
+    fn lock_timeout() {
+        let mutex = Mutex::new(());
+        let _guard = mutex.lock();
+        assert!(lock_for_close(&mutex, false).is_none());
+    }
+}